@@ -0,0 +1,239 @@
+use crate::config::compute_repo_id;
+use crate::git::GitRepo;
+use crate::metadata::{now_timestamp, WorktreeMetadata};
+use crate::status_cache;
+use crate::worktree::{slug_from_branch, Worktree};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const MANIFEST_FILENAME: &str = "archive.json";
+const BUNDLE_FILENAME: &str = "commits.bundle";
+const PATCH_FILENAME: &str = "changes.patch";
+
+/// Everything needed to recreate a removed worktree, stored next to the bundle and patch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub name: String,
+    pub branch: Option<String>,
+    pub head: String,
+    /// Where the worktree lived when it was archived
+    pub path: PathBuf,
+    pub created_at: i64,
+    /// Upstream as (remote, merge ref), restored onto the branch
+    pub upstream: Option<(String, String)>,
+    pub has_bundle: bool,
+    pub has_patch: bool,
+    pub metadata: Option<WorktreeMetadata>,
+}
+
+impl ArchiveManifest {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILENAME);
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read archive manifest {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse archive manifest {}", path.display()))
+    }
+}
+
+/// `~/.workty/archive/<repo-id>`
+pub fn archive_root(repo: &GitRepo) -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine home directory")?;
+    Ok(home
+        .join(".workty")
+        .join("archive")
+        .join(compute_repo_id(repo)))
+}
+
+/// Archive directories for this repository, oldest first.
+pub fn list_archives(repo: &GitRepo) -> Result<Vec<(PathBuf, ArchiveManifest)>> {
+    let root = archive_root(repo)?;
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut archives: Vec<(PathBuf, ArchiveManifest)> = std::fs::read_dir(&root)
+        .with_context(|| format!("Failed to read directory: {}", root.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|dir| ArchiveManifest::load(&dir).ok().map(|m| (dir, m)))
+        .collect();
+    archives.sort_by_key(|(_, m)| m.created_at);
+    Ok(archives)
+}
+
+/// Resolves `name` to an archive directory: a path, a directory name under the
+/// archive root, or the newest archive of the worktree with that name.
+pub fn find_archive(repo: &GitRepo, name: &str) -> Result<PathBuf> {
+    let as_path = PathBuf::from(name);
+    if as_path.join(MANIFEST_FILENAME).exists() {
+        return Ok(as_path);
+    }
+
+    let root = archive_root(repo)?;
+    if root.join(name).join(MANIFEST_FILENAME).exists() {
+        return Ok(root.join(name));
+    }
+
+    list_archives(repo)?
+        .into_iter()
+        .rev()
+        .find(|(_, m)| m.name == name || m.branch.as_deref() == Some(name))
+        .map(|(dir, _)| dir)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Archive '{}' not found. Run `git workty restore` to list archives.",
+                name
+            )
+        })
+}
+
+/// Saves unpushed commits as a bundle and staged, unstaged and untracked changes as
+/// a patch, so the worktree can be removed without losing work.
+pub fn archive_worktree(
+    repo: &GitRepo,
+    wt: &Worktree,
+    base: &str,
+    metadata: Option<&WorktreeMetadata>,
+) -> Result<PathBuf> {
+    let created_at = now_timestamp();
+    let dir = archive_root(repo)?.join(format!("{}-{}", slug_from_branch(wt.name()), created_at));
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    let has_bundle = write_bundle(wt, base, &dir.join(BUNDLE_FILENAME))?;
+    let has_patch = write_patch(wt, &dir)?;
+
+    let manifest = ArchiveManifest {
+        name: wt.name().to_string(),
+        branch: wt.branch_short.clone(),
+        head: wt.head.clone(),
+        path: wt.path.clone(),
+        created_at,
+        upstream: wt
+            .branch_short
+            .as_deref()
+            .and_then(|b| repo.branch_upstream_config(b)),
+        has_bundle,
+        has_patch,
+        metadata: metadata.cloned(),
+    };
+
+    let contents =
+        serde_json::to_string_pretty(&manifest).context("Failed to serialize archive manifest")?;
+    std::fs::write(dir.join(MANIFEST_FILENAME), contents)
+        .with_context(|| format!("Failed to write archive to {}", dir.display()))?;
+
+    Ok(dir)
+}
+
+/// Bundles commits not reachable from any remote-tracking branch or the local base.
+/// Returns false when there was nothing unpushed to save.
+fn write_bundle(wt: &Worktree, base: &str, bundle_path: &Path) -> Result<bool> {
+    let tip = wt.branch.as_deref().unwrap_or("HEAD");
+    let base_ref = format!("refs/heads/{}", base);
+
+    let mut exclude = vec!["--not", "--remotes"];
+    let base_exists = Command::new("git")
+        .current_dir(&wt.path)
+        .args(["rev-parse", "--verify", "--quiet", &base_ref])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if base_exists {
+        exclude.push(&base_ref);
+    }
+
+    let count = run_in(
+        &wt.path,
+        &[&["rev-list", "--count", tip], &exclude[..]].concat(),
+    )?;
+    if count.trim() == "0" {
+        return Ok(false);
+    }
+
+    let bundle_str = bundle_path.to_string_lossy();
+    run_in(
+        &wt.path,
+        &[
+            &["bundle", "create", "--quiet", &bundle_str, tip],
+            &exclude[..],
+        ]
+        .concat(),
+    )?;
+    Ok(true)
+}
+
+/// Diffs HEAD against a scratch index holding every change in the worktree, so the
+/// real index is left untouched. Returns false when the worktree was clean.
+fn write_patch(wt: &Worktree, dir: &Path) -> Result<bool> {
+    let scratch_index = dir.join("index.tmp");
+    let git = |args: &[&str]| -> Result<Vec<u8>> {
+        let output = Command::new("git")
+            .current_dir(&wt.path)
+            .env("GIT_INDEX_FILE", &scratch_index)
+            .args(args)
+            .output()
+            .context("Failed to execute git command")?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output.stdout)
+    };
+
+    let result = git(&["read-tree", "HEAD"])
+        .and_then(|_| git(&["add", "-A"]))
+        .and_then(|_| git(&["diff", "--cached", "--binary", "HEAD"]));
+    let _ = std::fs::remove_file(&scratch_index);
+    let patch = result?;
+
+    if patch.is_empty() {
+        return Ok(false);
+    }
+
+    let patch_path = dir.join(PATCH_FILENAME);
+    std::fs::write(&patch_path, patch)
+        .with_context(|| format!("Failed to write patch to {}", patch_path.display()))?;
+    Ok(true)
+}
+
+/// Recreates an archived worktree at `path`: unbundles commits, restores the branch
+/// and its upstream, and reapplies uncommitted changes.
+pub fn restore_archive(
+    repo: &GitRepo,
+    dir: &Path,
+    manifest: &ArchiveManifest,
+    path: &Path,
+) -> Result<()> {
+    if manifest.has_bundle {
+        let bundle = dir.join(BUNDLE_FILENAME);
+        repo.run_git(&["bundle", "unbundle", &bundle.to_string_lossy()])
+            .context("Failed to unbundle archived commits")?;
+    }
+
+    repo.recreate_worktree(
+        path,
+        manifest.branch.as_deref(),
+        &manifest.head,
+        manifest.upstream.as_ref(),
+    )?;
+
+    if manifest.has_patch {
+        let patch = dir.join(PATCH_FILENAME);
+        run_in(path, &["apply", "--binary", &patch.to_string_lossy()])
+            .context("Failed to reapply archived changes")?;
+        status_cache::invalidate(repo, &[path]);
+    }
+
+    Ok(())
+}
+
+fn run_in(dir: &Path, args: &[&str]) -> Result<String> {
+    crate::git::run_git_command(Some(dir), args)
+}
@@ -1,19 +1,32 @@
+use crate::archive::archive_worktree;
 use crate::config::Config;
-use crate::git::GitRepo;
-use crate::status::{get_all_statuses, is_worktree_dirty};
-use crate::ui::{print_info, print_success, print_warning};
+use crate::error::WorktyError;
+use crate::git::{GitRepo, MergeMethod};
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::metadata::MetadataStore;
+use crate::oplog::{record_removals, RemovedWorktree};
+use crate::status::{get_all_statuses, is_worktree_dirty, worktree_operation, BaseRefs, Operation};
+use crate::ui::{
+    self, print_info, print_success, print_warning, shorten_path, JsonRemoved, JsonSkipped,
+};
 use crate::worktree::{list_worktrees, Worktree};
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, Result};
 use dialoguer::Confirm;
 use is_terminal::IsTerminal;
-use std::process::Command;
+use serde::Serialize;
 
 pub struct CleanOptions {
     pub merged: bool,
     pub gone: bool,
     pub stale_days: Option<u32>,
     pub dry_run: bool,
+    /// Number of times --force was given: once removes worktrees with an operation in
+    /// progress, twice also locked ones
+    pub force: u8,
     pub yes: bool,
+    pub json: bool,
+    /// Overrides the `archive` config default
+    pub archive: Option<bool>,
 }
 
 pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
@@ -23,7 +36,8 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
 
     // Get statuses if we need them for --gone or --stale
     let statuses = if opts.gone || opts.stale_days.is_some() {
-        Some(get_all_statuses(repo, &worktrees))
+        let bases = BaseRefs::load(repo, &config, &worktrees);
+        Some(get_all_statuses(&worktrees, &bases))
     } else {
         None
     };
@@ -40,6 +54,10 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
     let has_filter = opts.merged || opts.gone || opts.stale_days.is_some();
 
     if !has_filter {
+        // Help text on stdout would break consumers expecting a JSON envelope
+        if opts.json {
+            bail!("No filter specified. Use --merged, --gone or --stale N.");
+        }
         print_info("No filter specified. Use one of:");
         println!("  --merged      Remove worktrees whose branches are merged into base");
         println!("  --gone        Remove worktrees whose upstream branch was deleted");
@@ -48,32 +66,35 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
         return Ok(());
     }
 
-    let candidates: Vec<&Worktree> = worktrees
+    // Each candidate carries how its branch was found to be merged, if it was
+    let candidates: Vec<(&Worktree, Option<MergeMethod>)> = worktrees
         .iter()
-        .filter(|wt| {
+        .filter_map(|wt| {
             if wt.path == current_path {
-                return false;
+                return None;
             }
 
             if wt.is_main_worktree(repo) {
-                return false;
+                return None;
             }
 
             if wt.detached {
-                return false;
+                return None;
             }
 
             if let Some(branch) = &wt.branch_short {
                 if branch == &config.base {
-                    return false;
+                    return None;
                 }
             }
 
             // Check --merged
             if opts.merged {
                 if let Some(branch) = &wt.branch_short {
-                    if matches!(repo.is_merged(branch, &config.base), Ok(true)) {
-                        return true;
+                    if let Ok(Some(method)) =
+                        repo.merge_method(branch, &config.base, &config.remote)
+                    {
+                        return Some((wt, Some(method)));
                     }
                 }
             }
@@ -82,7 +103,7 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
             if opts.gone {
                 if let Some(status) = get_status(wt) {
                     if status.upstream_gone {
-                        return true;
+                        return Some((wt, None));
                     }
                 }
             }
@@ -93,42 +114,69 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
                     if let Some(seconds) = status.last_commit_time {
                         let stale_seconds = (days as i64) * 24 * 60 * 60;
                         if seconds > stale_seconds {
-                            return true;
+                            return Some((wt, None));
                         }
                     }
                 }
             }
 
-            false
+            None
         })
         .collect();
 
     if candidates.is_empty() {
+        if opts.json {
+            ui::print_json("clean", &JsonCleanOutput::new(&[], opts.dry_run));
+        }
         print_info("No worktrees to clean up.");
         return Ok(());
     }
 
     // Compute dirty status once per candidate to avoid redundant checks
-    let candidates_with_dirty: Vec<(&Worktree, bool)> = candidates
+    let candidates: Vec<Candidate> = candidates
         .into_iter()
-        .map(|wt| {
-            let is_dirty = is_worktree_dirty(wt);
-            (wt, is_dirty)
+        .map(|(wt, method)| Candidate {
+            wt,
+            method,
+            dirty: is_worktree_dirty(wt),
+            operation: worktree_operation(wt),
         })
         .collect();
 
-    println!("Worktrees to remove:");
-    for (wt, is_dirty) in &candidates_with_dirty {
-        let dirty_str = if *is_dirty { " (dirty)" } else { "" };
-        println!("  - {}{}", wt.name(), dirty_str);
+    let mut output = JsonCleanOutput::new(&candidates, opts.dry_run);
+    if !opts.json {
+        println!("Worktrees to remove:");
+        for c in &candidates {
+            let merged_str = c
+                .method
+                .map(|m| format!(" (merged: {})", m))
+                .unwrap_or_default();
+            let dirty_str = if c.dirty { " (dirty)" } else { "" };
+            let operation_str = c
+                .operation
+                .map(|op| format!(" ({})", op.label()))
+                .unwrap_or_default();
+            let locked_str = if c.wt.locked { " (locked)" } else { "" };
+            println!(
+                "  - {}{}{}{}{}",
+                c.wt.name(),
+                merged_str,
+                dirty_str,
+                operation_str,
+                locked_str
+            );
+        }
     }
 
     if opts.dry_run {
+        if opts.json {
+            ui::print_json("clean", &output);
+        }
         print_info("Dry run - no worktrees removed.");
         return Ok(());
     }
 
-    let dirty_count = candidates_with_dirty.iter().filter(|(_, d)| *d).count();
+    let dirty_count = candidates.iter().filter(|c| c.dirty).count();
     if dirty_count > 0 {
         print_warning(&format!(
             "{} worktree(s) have uncommitted changes and will be skipped.",
@@ -136,14 +184,49 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
         ));
     }
 
-    let clean_candidates: Vec<&Worktree> = candidates_with_dirty
+    let allow_in_progress = opts.force >= 1;
+    let in_progress_count = candidates
         .iter()
-        .filter(|(_, is_dirty)| !is_dirty)
-        .map(|(wt, _)| *wt)
-        .collect();
+        .filter(|c| !c.dirty && c.operation.is_some() && !allow_in_progress)
+        .count();
+    if in_progress_count > 0 {
+        print_warning(&format!(
+            "{} worktree(s) are in the middle of a rebase, merge or similar and will be skipped. Pass --force to remove them.",
+            in_progress_count
+        ));
+    }
+
+    let allow_locked = opts.force >= 2;
+    let locked_count = candidates
+        .iter()
+        .filter(|c| {
+            !c.dirty && (c.operation.is_none() || allow_in_progress) && c.wt.locked && !allow_locked
+        })
+        .count();
+    if locked_count > 0 {
+        print_warning(&format!(
+            "{} worktree(s) are locked and will be skipped. Pass --force twice to remove them.",
+            locked_count
+        ));
+    }
+
+    let mut clean_candidates: Vec<&Worktree> = Vec::new();
+    for c in &candidates {
+        match c.operation {
+            _ if c.dirty => output.skip(c.wt, "dirty"),
+            Some(operation) if !allow_in_progress => output.skip(c.wt, operation.label()),
+            _ if c.wt.locked && !allow_locked => output.skip(c.wt, "locked"),
+            _ => clean_candidates.push(c.wt),
+        }
+    }
 
     if clean_candidates.is_empty() {
-        print_info("All candidate worktrees have uncommitted changes. Nothing to remove.");
+        if opts.json {
+            ui::print_json("clean", &output);
+        }
+        print_info(
+            "All candidate worktrees are dirty, locked or mid-operation. Nothing to remove.",
+        );
         return Ok(());
     }
 
@@ -154,40 +237,148 @@ pub fn execute(repo: &GitRepo, opts: CleanOptions) -> Result<()> {
             .interact()?;
 
         if !confirm {
-            eprintln!("Aborted.");
-            return Ok(());
+            return Err(WorktyError::Declined.into());
         }
     } else if !opts.yes {
         bail!("Non-interactive mode requires --yes flag for destructive operations");
     }
 
-    let mut removed = 0;
+    let archive = opts.archive.unwrap_or(config.archive);
+    let mut store = MetadataStore::load(repo)?;
+    let mut removed = Vec::new();
     for wt in clean_candidates {
-        let path_str = wt
-            .path
-            .to_str()
-            .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {:?}", wt.path))?;
-
-        let output = Command::new("git")
-            .current_dir(&repo.root)
-            .args(["worktree", "remove", path_str])
-            .output()
-            .context("Failed to remove worktree")?;
-
-        if output.status.success() {
-            print_success(&format!("Removed worktree '{}'", wt.name()));
-            removed += 1;
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            print_warning(&format!(
-                "Failed to remove '{}': {}",
-                wt.name(),
-                stderr.trim()
-            ));
+        let hook_result = run_hook(
+            &config,
+            Hook::PreRemove,
+            &HookContext {
+                worktree_path: &wt.path,
+                branch: wt.branch_short.as_deref(),
+                base: Some(&config.base),
+                repo_root: &repo.root,
+            },
+        );
+        if let Err(e) = hook_result {
+            print_warning(&format!("Skipping '{}': {:#}", wt.name(), e));
+            output.skip(wt, &format!("{:#}", e));
+            continue;
+        }
+
+        let mut archive_dir = None;
+        if archive {
+            match archive_worktree(repo, wt, &config.base, store.get(&wt.path)) {
+                Ok(dir) => {
+                    print_info(&format!(
+                        "Archived '{}' to {}",
+                        wt.name(),
+                        shorten_path(&dir)
+                    ));
+                    archive_dir = Some(dir);
+                }
+                Err(e) => {
+                    print_warning(&format!(
+                        "Skipping '{}': failed to archive: {:#}",
+                        wt.name(),
+                        e
+                    ));
+                    output.skip(wt, &format!("failed to archive: {:#}", e));
+                    continue;
+                }
+            }
+        }
+
+        let record = RemovedWorktree::capture(repo, wt, store.get(&wt.path));
+        let meta = store.remove(&wt.path);
+
+        let force = if wt.locked { 2 } else { 0 };
+        match repo.remove_worktree(&wt.path, force) {
+            Ok(()) => {
+                print_success(&format!("Removed worktree '{}'", wt.name()));
+                output.removed.push(JsonRemoved {
+                    name: wt.name().to_string(),
+                    path: wt.path.to_string_lossy().into_owned(),
+                    branch: wt.branch_short.clone(),
+                    archive: archive_dir.map(|d| d.to_string_lossy().into_owned()),
+                });
+                removed.push(record);
+            }
+            Err(e) => {
+                if let Some(meta) = meta {
+                    *store.entry(&wt.path) = meta;
+                }
+                print_warning(&format!("Failed to remove '{}': {}", wt.name(), e));
+                output.skip(wt, &e.to_string());
+            }
         }
     }
 
-    print_info(&format!("Cleaned up {} worktree(s).", removed));
+    store.save()?;
+
+    let count = removed.len();
+    record_removals(repo, "clean", removed);
+
+    if opts.json {
+        ui::print_json("clean", &output);
+    }
+    print_info(&format!("Cleaned up {} worktree(s).", count));
+    if count > 0 {
+        print_info("Run `git workty undo` to bring them back.");
+    }
 
     Ok(())
 }
+
+#[derive(Serialize)]
+struct JsonCandidate {
+    name: String,
+    path: String,
+    branch: Option<String>,
+    merge_method: Option<MergeMethod>,
+    dirty: bool,
+    operation: Option<Operation>,
+    locked: bool,
+}
+
+/// A worktree matching the filters, with what decides whether it can be removed.
+struct Candidate<'a> {
+    wt: &'a Worktree,
+    method: Option<MergeMethod>,
+    dirty: bool,
+    operation: Option<Operation>,
+}
+
+#[derive(Serialize)]
+struct JsonCleanOutput {
+    dry_run: bool,
+    candidates: Vec<JsonCandidate>,
+    removed: Vec<JsonRemoved>,
+    skipped: Vec<JsonSkipped>,
+}
+
+impl JsonCleanOutput {
+    fn new(candidates: &[Candidate], dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            candidates: candidates
+                .iter()
+                .map(|c| JsonCandidate {
+                    name: c.wt.name().to_string(),
+                    path: c.wt.path.to_string_lossy().into_owned(),
+                    branch: c.wt.branch_short.clone(),
+                    merge_method: c.method,
+                    dirty: c.dirty,
+                    operation: c.operation,
+                    locked: c.wt.locked,
+                })
+                .collect(),
+            removed: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    fn skip(&mut self, wt: &Worktree, reason: &str) {
+        self.skipped.push(JsonSkipped {
+            name: wt.name().to_string(),
+            reason: reason.to_string(),
+        });
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::{
+    config_path, flatten, set_value, unset_value, user_config_path, validate_file, Config,
+    ConfigOrigin,
+};
+use crate::git::GitRepo;
+use crate::ui::{print_info, print_success};
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub enum ConfigAction {
+    List { show_origin: bool },
+    Get { key: String },
+    Set { key: String, value: String },
+    Unset { key: String },
+    Edit,
+}
+
+pub struct ConfigOptions {
+    pub action: ConfigAction,
+    /// Write to the user-level file instead of `.git/workty.toml`
+    pub user: bool,
+}
+
+pub fn execute(repo: &GitRepo, opts: ConfigOptions) -> Result<()> {
+    match opts.action {
+        ConfigAction::List { show_origin } => list(repo, show_origin),
+        ConfigAction::Get { key } => get(repo, &key),
+        ConfigAction::Set { key, value } => {
+            let path = target_path(repo, opts.user)?;
+            set_value(&path, &key, &value)?;
+            print_success(&format!("Set {} in {}", key, path.display()));
+            Ok(())
+        }
+        ConfigAction::Unset { key } => {
+            let path = target_path(repo, opts.user)?;
+            if unset_value(&path, &key)? {
+                print_success(&format!("Unset {} in {}", key, path.display()));
+            } else {
+                print_info(&format!("{} is not set in {}", key, path.display()));
+            }
+            Ok(())
+        }
+        ConfigAction::Edit => edit(&target_path(repo, opts.user)?),
+    }
+}
+
+fn list(repo: &GitRepo, show_origin: bool) -> Result<()> {
+    let (config, origins) = Config::load_with_origins(repo)?;
+
+    for (key, value) in flatten(&config) {
+        if show_origin {
+            let origin = origins.get(&key).unwrap_or(&ConfigOrigin::Default);
+            println!("{}\t{} = {}", origin, key, value);
+        } else {
+            println!("{} = {}", key, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn get(repo: &GitRepo, key: &str) -> Result<()> {
+    let config = Config::load(repo)?;
+
+    match flatten(&config).into_iter().find(|(k, _)| k == key) {
+        Some((_, toml::Value::String(s))) => println!("{}", s),
+        Some((_, value)) => println!("{}", value),
+        None => bail!("Config key '{}' is not set", key),
+    }
+
+    Ok(())
+}
+
+/// Edits a copy of the file and only replaces the real one once the copy validates,
+/// so a typo can't leave every other command failing to load the config.
+fn edit(path: &Path) -> Result<()> {
+    let draft = path.with_extension("edit.toml");
+    if draft.exists() {
+        print_info(&format!("Resuming unsaved edits from {}", draft.display()));
+    } else {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let contents = if path.exists() {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+        } else {
+            String::new()
+        };
+        std::fs::write(&draft, contents)
+            .with_context(|| format!("Failed to create {}", draft.display()))?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&draft)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", editor))?;
+
+    if !status.success() {
+        bail!(
+            "Editor '{}' exited with {}; {} was left unchanged",
+            editor,
+            status,
+            path.display()
+        );
+    }
+
+    validate_file(&draft).with_context(|| {
+        format!(
+            "Your edits are not a valid workty config, so {} was left unchanged. \
+             They are kept in {}; run `git workty config edit` again to fix them",
+            path.display(),
+            draft.display()
+        )
+    })?;
+
+    std::fs::rename(&draft, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+    print_success(&format!("Saved {}", path.display()));
+    Ok(())
+}
+
+fn target_path(repo: &GitRepo, user: bool) -> Result<PathBuf> {
+    if user {
+        user_config_path().context("Could not determine user config directory")
+    } else {
+        Ok(config_path(repo))
+    }
+}
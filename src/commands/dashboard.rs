@@ -0,0 +1,523 @@
+use crate::commands::list::sort_worktrees;
+use crate::commands::{fetch, rm, sync};
+use crate::config::Config;
+use crate::git::GitRepo;
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::status::{BaseRefs, WorktreeStatus};
+use crate::status_cache::{cached_statuses, refresh_statuses};
+use crate::ui::{
+    dirty_width, format_dirty, format_rebase, format_sync, format_time, shorten_path, Icons,
+    UiOptions,
+};
+use crate::worktree::{list_worktrees, Worktree};
+use anyhow::{bail, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use is_terminal::IsTerminal;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+type Rows = Vec<(Worktree, WorktreeStatus)>;
+
+struct State {
+    rows: Rows,
+    selected: usize,
+    filter: String,
+    filtering: bool,
+    message: Option<String>,
+    loading: bool,
+}
+
+impl State {
+    fn visible(&self) -> Vec<&(Worktree, WorktreeStatus)> {
+        let needle = self.filter.to_lowercase();
+        self.rows
+            .iter()
+            .filter(|(wt, _)| needle.is_empty() || wt.name().to_lowercase().contains(&needle))
+            .collect()
+    }
+
+    fn selected_worktree(&self) -> Option<Worktree> {
+        self.visible().get(self.selected).map(|(wt, _)| wt.clone())
+    }
+
+    fn clamp_selection(&mut self) {
+        let len = self.visible().len();
+        if self.selected >= len {
+            self.selected = len.saturating_sub(1);
+        }
+    }
+}
+
+enum Action {
+    None,
+    Quit,
+    Switch(Worktree),
+    Remove(Worktree),
+    Sync,
+    Fetch,
+    Open(Worktree),
+    Refresh,
+}
+
+/// Restores the terminal even if drawing fails or a panic unwinds.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stderr(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub fn execute(repo: &GitRepo, opts: &UiOptions) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        bail!("Cannot run the dashboard in non-TTY. Use `git workty list` instead.");
+    }
+
+    let config = Config::load(repo)?;
+    let current_path = std::env::current_dir().unwrap_or_default();
+
+    let (status_tx, status_rx) = mpsc::channel::<Rows>();
+    let (refresh_tx, refresh_rx) = mpsc::channel::<()>();
+    let stop = AtomicBool::new(false);
+
+    let chosen = std::thread::scope(|scope| -> Result<Option<Worktree>> {
+        let stop = &stop;
+        let current_path = &current_path;
+        let config = &config;
+        scope.spawn(move || {
+            // Paint from the cache first, then replace it with fresh statuses
+            if let Ok(worktrees) = list_worktrees(repo) {
+                let bases = BaseRefs::load(repo, config, &worktrees);
+                let (rows, _) = cached_statuses(repo, &worktrees, &bases);
+                let _ = status_tx.send(sort_worktrees(rows, current_path));
+            }
+
+            while !stop.load(Ordering::Relaxed) {
+                if let Ok(worktrees) = list_worktrees(repo) {
+                    let bases = BaseRefs::load(repo, config, &worktrees);
+                    let rows = refresh_statuses(repo, &worktrees, &bases);
+                    if status_tx.send(sort_worktrees(rows, current_path)).is_err() {
+                        break;
+                    }
+                }
+                // Wake up early when an action asks for fresh data
+                let _ = refresh_rx.recv_timeout(REFRESH_INTERVAL);
+                while refresh_rx.try_recv().is_ok() {}
+            }
+        });
+
+        let result = run_loop(repo, config, opts, &status_rx, &refresh_tx);
+        stop.store(true, Ordering::Relaxed);
+        let _ = refresh_tx.send(());
+        result
+    })?;
+
+    if let Some(wt) = chosen {
+        run_hook(
+            &config,
+            Hook::PostSwitch,
+            &HookContext {
+                worktree_path: &wt.path,
+                branch: wt.branch_short.as_deref(),
+                base: Some(&config.base),
+                repo_root: &repo.root,
+            },
+        )?;
+        println!("{}", wt.path.display());
+    }
+
+    Ok(())
+}
+
+fn run_loop(
+    repo: &GitRepo,
+    config: &Config,
+    opts: &UiOptions,
+    status_rx: &mpsc::Receiver<Rows>,
+    refresh_tx: &mpsc::Sender<()>,
+) -> Result<Option<Worktree>> {
+    let mut guard = Some(TerminalGuard::enter()?);
+    let mut state = State {
+        rows: Vec::new(),
+        selected: 0,
+        filter: String::new(),
+        filtering: false,
+        message: None,
+        loading: true,
+    };
+
+    let mut redraw = true;
+
+    loop {
+        while let Ok(rows) = status_rx.try_recv() {
+            state.rows = rows;
+            state.loading = false;
+            state.clamp_selection();
+            redraw = true;
+        }
+
+        if redraw {
+            draw(&state, repo, opts)?;
+            redraw = false;
+        }
+
+        if !event::poll(POLL_INTERVAL)? {
+            continue;
+        }
+
+        redraw = true;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match handle_key(&mut state, key) {
+            Action::None => {}
+            Action::Quit => return Ok(None),
+            Action::Switch(wt) => return Ok(Some(wt)),
+            Action::Refresh => {
+                state.loading = true;
+                let _ = refresh_tx.send(());
+            }
+            Action::Open(wt) => {
+                state.message = Some(match &config.open_cmd {
+                    Some(open_cmd) => match Command::new(open_cmd).arg(&wt.path).spawn() {
+                        Ok(_) => format!("Opened {} in {}", wt.name(), open_cmd),
+                        Err(e) => format!("Failed to run {}: {}", open_cmd, e),
+                    },
+                    None => "No open_cmd configured".to_string(),
+                });
+            }
+            Action::Remove(wt) => {
+                let name = wt.name().to_string();
+                state.message = Some(suspend(&mut guard, || {
+                    rm::execute(
+                        repo,
+                        rm::RmOptions {
+                            name: name.clone(),
+                            force: 0,
+                            delete_branch: false,
+                            yes: false,
+                            archive: None,
+                            json: false,
+                        },
+                    )
+                })?);
+                state.loading = true;
+                let _ = refresh_tx.send(());
+            }
+            Action::Sync => {
+                state.message = Some(suspend(&mut guard, || {
+                    sync::execute(
+                        repo,
+                        sync::SyncOptions {
+                            dry_run: false,
+                            fetch: false,
+                            json: false,
+                            strategy: sync::SyncStrategy::Rebase,
+                            onto_base: false,
+                            jobs: None,
+                            force: false,
+                        },
+                    )
+                })?);
+                state.loading = true;
+                let _ = refresh_tx.send(());
+            }
+            Action::Fetch => {
+                state.message = Some(suspend(&mut guard, || fetch::execute(repo, false, false))?);
+                state.loading = true;
+                let _ = refresh_tx.send(());
+            }
+        }
+    }
+}
+
+fn handle_key(state: &mut State, key: KeyEvent) -> Action {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Action::Quit;
+    }
+
+    if state.filtering {
+        match key.code {
+            KeyCode::Esc => {
+                state.filter.clear();
+                state.filtering = false;
+            }
+            KeyCode::Enter => state.filtering = false,
+            KeyCode::Backspace => {
+                state.filter.pop();
+            }
+            KeyCode::Char(c) => state.filter.push(c),
+            _ => {}
+        }
+        state.selected = 0;
+        return Action::None;
+    }
+
+    state.message = None;
+    let len = state.visible().len();
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.selected = state.selected.saturating_sub(1);
+            Action::None
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            if state.selected + 1 < len {
+                state.selected += 1;
+            }
+            Action::None
+        }
+        KeyCode::Char('/') => {
+            state.filtering = true;
+            Action::None
+        }
+        KeyCode::Char('r') => Action::Refresh,
+        KeyCode::Char('s') => Action::Sync,
+        KeyCode::Char('f') => Action::Fetch,
+        KeyCode::Enter => state
+            .selected_worktree()
+            .map(Action::Switch)
+            .unwrap_or(Action::None),
+        KeyCode::Char('d') => state
+            .selected_worktree()
+            .map(Action::Remove)
+            .unwrap_or(Action::None),
+        KeyCode::Char('o') => state
+            .selected_worktree()
+            .map(Action::Open)
+            .unwrap_or(Action::None),
+        _ => Action::None,
+    }
+}
+
+/// Leaves the full-screen view so a command can print and prompt normally.
+fn suspend(guard: &mut Option<TerminalGuard>, run: impl FnOnce() -> Result<()>) -> Result<String> {
+    guard.take();
+
+    let message = match run() {
+        Ok(()) => "Done".to_string(),
+        Err(e) => format!("error: {:#}", e),
+    };
+
+    eprintln!("\nPress any key to return to the dashboard...");
+    terminal::enable_raw_mode()?;
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                break;
+            }
+        }
+    }
+    terminal::disable_raw_mode()?;
+
+    *guard = Some(TerminalGuard::enter()?);
+    Ok(message)
+}
+
+fn draw(state: &State, repo: &GitRepo, opts: &UiOptions) -> Result<()> {
+    let mut out = io::stderr();
+    let (width, height) = terminal::size()?;
+    let width = width as usize;
+    let icons = Icons::from_options(opts);
+    let plain = UiOptions {
+        color: false,
+        ..*opts
+    };
+
+    queue!(
+        out,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+
+    let title = format!(
+        "workty  {}{}",
+        shorten_path(&repo.main_worktree_path()),
+        if state.loading {
+            "  (refreshing...)"
+        } else {
+            ""
+        }
+    );
+    queue!(
+        out,
+        SetAttribute(Attribute::Bold),
+        Print(truncate(&title, width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    let visible = state.visible();
+    let max_name_len = visible
+        .iter()
+        .map(|(wt, _)| wt.name().len())
+        .max()
+        .unwrap_or(10)
+        .max(6);
+    let dirty_width = dirty_width(visible.iter().map(|(_, status)| status), &icons);
+
+    let header = format!(
+        "  {:width$}  {:>dirty_width$}  {:>6}  {:>5}  {:>6}  PATH",
+        "BRANCH",
+        "DIRTY",
+        "SYNC",
+        "AGE",
+        "REBASE",
+        width = max_name_len,
+        dirty_width = dirty_width
+    );
+    queue!(
+        out,
+        cursor::MoveTo(0, 2),
+        SetAttribute(Attribute::Dim),
+        Print(truncate(&header, width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    let current_path: PathBuf = std::env::current_dir().unwrap_or_default();
+    let list_height = (height as usize).saturating_sub(6);
+    let offset = state.selected.saturating_sub(list_height.saturating_sub(1));
+
+    for (row, (wt, status)) in visible.iter().enumerate().skip(offset).take(list_height) {
+        let marker = if wt.path == current_path {
+            icons.current
+        } else {
+            " "
+        };
+        let line = format!(
+            "{} {:name_width$}  {}  {:>6}  {:>5}  {:>6}  {}",
+            marker,
+            wt.name(),
+            format_dirty(status, &icons, &plain, dirty_width),
+            format_sync(status, &icons),
+            format_time(status.last_commit_time),
+            format_rebase(status, &icons, &plain),
+            shorten_path(&wt.path),
+            name_width = max_name_len
+        );
+
+        queue!(out, cursor::MoveTo(0, (row - offset + 3) as u16))?;
+        if row == state.selected {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        } else if (status.is_dirty() || status.operation.is_some()) && opts.color {
+            queue!(out, SetForegroundColor(Color::Yellow))?;
+        }
+        queue!(
+            out,
+            Print(truncate(&line, width)),
+            SetAttribute(Attribute::Reset),
+            ResetColor
+        )?;
+    }
+
+    if visible.is_empty() && !state.loading {
+        queue!(out, cursor::MoveTo(0, 3), Print("  No matching worktrees"))?;
+    }
+
+    let footer_row = height.saturating_sub(2);
+    if state.filtering || !state.filter.is_empty() {
+        queue!(
+            out,
+            cursor::MoveTo(0, footer_row),
+            Print(truncate(&format!("/{}", state.filter), width))
+        )?;
+    } else if let Some(message) = &state.message {
+        queue!(
+            out,
+            cursor::MoveTo(0, footer_row),
+            Print(truncate(message, width))
+        )?;
+    }
+
+    let help = "enter cd  d rm  s sync  f fetch  o open  / filter  r refresh  q quit";
+    queue!(
+        out,
+        cursor::MoveTo(0, height.saturating_sub(1)),
+        SetAttribute(Attribute::Dim),
+        Print(truncate(help, width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    out.flush()?;
+    Ok(())
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(branch: &str) -> (Worktree, WorktreeStatus) {
+        (
+            Worktree {
+                path: PathBuf::from(format!("/tmp/{}", branch)),
+                head: String::new(),
+                branch: Some(format!("refs/heads/{}", branch)),
+                branch_short: Some(branch.to_string()),
+                detached: false,
+                locked: false,
+                lock_reason: None,
+                prunable: false,
+            },
+            WorktreeStatus::default(),
+        )
+    }
+
+    fn press(state: &mut State, code: KeyCode) -> Action {
+        handle_key(state, KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_filter_and_select() {
+        let mut state = State {
+            rows: vec![row("main"), row("feat/login"), row("feat/logout")],
+            selected: 0,
+            filter: String::new(),
+            filtering: false,
+            message: None,
+            loading: false,
+        };
+
+        press(&mut state, KeyCode::Char('/'));
+        for c in "log".chars() {
+            press(&mut state, KeyCode::Char(c));
+        }
+        press(&mut state, KeyCode::Enter);
+        assert_eq!(state.visible().len(), 2);
+
+        press(&mut state, KeyCode::Char('j'));
+        press(&mut state, KeyCode::Char('j'));
+        match press(&mut state, KeyCode::Enter) {
+            Action::Switch(wt) => assert_eq!(wt.name(), "feat/logout"),
+            _ => panic!("Enter should switch to the selected worktree"),
+        }
+
+        assert!(matches!(
+            press(&mut state, KeyCode::Char('q')),
+            Action::Quit
+        ));
+    }
+}
@@ -1,69 +1,185 @@
 use crate::config::{config_exists, Config};
 use crate::git::{is_git_installed, is_in_git_repo, GitRepo};
-use crate::worktree::list_worktrees;
+use crate::metadata::MetadataStore;
+use crate::ui;
+use crate::worktree::{check_same_path, list_worktrees, Worktree};
+use dialoguer::Confirm;
+use is_terminal::IsTerminal;
 use owo_colors::OwoColorize;
-use std::path::Path;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn execute(start_path: Option<&Path>) {
-    let mut all_ok = true;
+/// Outcome of one doctor check, as reported by `--json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+    /// Optional and not available, e.g. `gh` not installed
+    Skipped,
+}
+
+#[derive(Serialize)]
+struct Check {
+    id: &'static str,
+    status: CheckStatus,
+    message: String,
+    hint: Option<String>,
+}
+
+/// Collects check results, printing them as they come unless `--json` was given.
+#[derive(Serialize)]
+struct Report {
+    ok: bool,
+    checks: Vec<Check>,
+    #[serde(skip)]
+    json: bool,
+}
+
+impl Report {
+    fn add(&mut self, id: &'static str, status: CheckStatus, message: String) {
+        if !self.json {
+            match status {
+                CheckStatus::Ok => eprintln!("{} {}", "✓".green(), message),
+                CheckStatus::Fail => eprintln!("{} {}", "✗".red(), message),
+                CheckStatus::Warn => eprintln!("{} {}", "!".yellow(), message),
+                CheckStatus::Skipped => eprintln!("  {} {}", "○".dimmed(), message),
+            }
+        }
+        if status == CheckStatus::Fail {
+            self.ok = false;
+        }
+        self.checks.push(Check {
+            id,
+            status,
+            message,
+            hint: None,
+        });
+    }
 
-    print_check("Git installed", is_git_installed(), &mut all_ok);
+    fn check(&mut self, id: &'static str, name: &str, ok: bool) {
+        let status = if ok {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Fail
+        };
+        self.add(id, status, name.to_string());
+    }
+
+    /// Attaches a hint to the most recent check.
+    fn hint(&mut self, hint: String) {
+        if !self.json {
+            eprintln!("  {}: {}", "hint".cyan(), hint);
+        }
+        if let Some(check) = self.checks.last_mut() {
+            check.hint = Some(hint);
+        }
+    }
+
+    fn info(&self, msg: &str) {
+        if !self.json {
+            eprintln!("  {}", msg);
+        }
+    }
+}
+
+pub fn execute(start_path: Option<&Path>, yes: bool, json: bool) {
+    let mut report = Report {
+        ok: true,
+        checks: Vec::new(),
+        json,
+    };
+    run_checks(&mut report, start_path, yes);
+
+    if json {
+        ui::print_json("doctor", &report);
+        return;
+    }
+
+    eprintln!();
+    if report.ok {
+        eprintln!("{}", "All checks passed! ✓".green().bold());
+    } else {
+        eprintln!("{}", "Some checks failed. See hints above.".yellow());
+    }
+}
+
+fn run_checks(report: &mut Report, start_path: Option<&Path>, yes: bool) {
+    report.check("git_installed", "Git installed", is_git_installed());
 
     let cwd = start_path
         .map(|p| p.to_path_buf())
         .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
 
     let in_repo = is_in_git_repo(&cwd);
-    print_check("Inside Git repository", in_repo, &mut all_ok);
+    report.check("in_repository", "Inside Git repository", in_repo);
 
     if !in_repo {
-        eprintln!(
-            "\n{}: Run this command from inside a Git repository.",
-            "hint".cyan()
-        );
+        report.hint("Run this command from inside a Git repository.".to_string());
         return;
     }
 
     let repo = match GitRepo::discover(start_path) {
         Ok(r) => r,
         Err(e) => {
-            print_fail(&format!("Failed to discover repo: {}", e));
+            report.add(
+                "discover_repository",
+                CheckStatus::Fail,
+                format!("Failed to discover repo: {}", e),
+            );
             return;
         }
     };
 
-    eprintln!("  Repository root: {}", repo.root.display());
-    eprintln!("  Common dir: {}", repo.common_dir.display());
+    report.info(&format!("Repository root: {}", repo.root.display()));
+    report.info(&format!("Common dir: {}", repo.common_dir.display()));
 
     let worktrees = list_worktrees(&repo);
-    print_check("Can list worktrees", worktrees.is_ok(), &mut all_ok);
+    report.check("list_worktrees", "Can list worktrees", worktrees.is_ok());
 
     if let Ok(wts) = &worktrees {
-        eprintln!("  Found {} worktree(s)", wts.len());
+        report.info(&format!("Found {} worktree(s)", wts.len()));
 
         let prunable: Vec<_> = wts.iter().filter(|wt| wt.prunable).collect();
         if !prunable.is_empty() {
-            print_warn(&format!("{} prunable worktree(s) found", prunable.len()));
-            eprintln!("  {}: Run `git worktree prune` to clean up.", "hint".cyan());
+            report.add(
+                "prunable_worktrees",
+                CheckStatus::Warn,
+                format!("{} prunable worktree(s) found", prunable.len()),
+            );
+            report.hint("Run `git worktree prune` to clean up.".to_string());
         }
     }
 
-    print_check("Config exists", config_exists(&repo), &mut all_ok);
+    let has_config = config_exists(&repo);
+    report.check("config_exists", "Config exists", has_config);
 
-    if config_exists(&repo) {
+    if has_config {
         match Config::load(&repo) {
             Ok(config) => {
-                eprintln!("  Base branch: {}", config.base);
-                eprintln!("  Workspace root: {}", config.root);
+                report.info(&format!("Base branch: {}", config.base));
+                report.info(&format!("Workspace root: {}", config.root));
             }
             Err(e) => {
-                print_fail(&format!("Config parse error: {}", e));
-                all_ok = false;
+                report.add(
+                    "config_parse",
+                    CheckStatus::Fail,
+                    format!("Config parse error: {}", e),
+                );
             }
         }
     } else {
-        eprintln!("  Using default config (no workty.toml found)");
+        report.info("Using default config (no workty.toml found)");
+    }
+
+    if let Ok(config) = Config::load(&repo) {
+        check_base(report, &repo, &config);
+    }
+
+    if let (Ok(config), Ok(wts)) = (Config::load(&repo), &worktrees) {
+        check_layout(report, &repo, &config, wts, yes);
     }
 
     let gh_installed = Command::new("gh")
@@ -73,7 +189,11 @@ pub fn execute(start_path: Option<&Path>) {
         .unwrap_or(false);
 
     if gh_installed {
-        eprintln!("  {} GitHub CLI (gh) available", "✓".green());
+        report.add(
+            "gh_installed",
+            CheckStatus::Ok,
+            "GitHub CLI (gh) available".to_string(),
+        );
 
         let gh_auth = Command::new("gh")
             .args(["auth", "status"])
@@ -82,42 +202,209 @@ pub fn execute(start_path: Option<&Path>) {
             .unwrap_or(false);
 
         if gh_auth {
-            eprintln!("  {} GitHub CLI authenticated", "✓".green());
+            report.add(
+                "gh_authenticated",
+                CheckStatus::Ok,
+                "GitHub CLI authenticated".to_string(),
+            );
         } else {
-            eprintln!("  {} GitHub CLI not authenticated", "○".yellow());
-            eprintln!(
-                "  {}: Run `gh auth login` to enable PR features.",
-                "hint".cyan()
+            report.add(
+                "gh_authenticated",
+                CheckStatus::Warn,
+                "GitHub CLI not authenticated".to_string(),
             );
+            report.hint("Run `gh auth login` to enable PR features.".to_string());
         }
     } else {
-        eprintln!(
-            "  {} GitHub CLI (gh) not installed (optional)",
-            "○".dimmed()
+        report.add(
+            "gh_installed",
+            CheckStatus::Skipped,
+            "GitHub CLI (gh) not installed (optional)".to_string(),
         );
     }
+}
 
-    eprintln!();
-    if all_ok {
-        eprintln!("{}", "All checks passed! ✓".green().bold());
-    } else {
-        eprintln!("{}", "Some checks failed. See hints above.".yellow());
+fn check_base(report: &mut Report, repo: &GitRepo, config: &Config) {
+    // Nothing to compare against for repos without the remote
+    if !repo.has_remote(&config.remote) {
+        return;
+    }
+
+    match repo.refresh_remote_head(&config.remote) {
+        Some(branch) => {
+            let remote_ref = format!("{}/{}", config.remote, branch);
+            if config.base == branch || config.base == remote_ref {
+                report.add(
+                    "base_matches_remote",
+                    CheckStatus::Ok,
+                    format!("Base matches {}'s default branch", config.remote),
+                );
+            } else {
+                report.add(
+                    "base_matches_remote",
+                    CheckStatus::Warn,
+                    format!(
+                        "Configured base '{}' differs from {}'s default branch '{}'",
+                        config.base, config.remote, branch
+                    ),
+                );
+                // The base is detected from the local remote HEAD, which may be stale
+                if repo.local_remote_head(&config.remote).as_deref() != Some(branch.as_str()) {
+                    report.hint(format!(
+                        "Run `git remote set-head {} --auto` to record it locally, \
+                         or `git workty config set base {}`.",
+                        config.remote, branch
+                    ));
+                } else {
+                    report.hint(format!(
+                        "Run `git workty config set base {}` if the remote is right.",
+                        branch
+                    ));
+                }
+            }
+        }
+        None => report.add(
+            "base_matches_remote",
+            CheckStatus::Skipped,
+            format!("Could not determine {}'s default branch", config.remote),
+        ),
     }
 }
 
-fn print_check(name: &str, ok: bool, all_ok: &mut bool) {
-    if ok {
-        eprintln!("{} {}", "✓".green(), name);
+fn check_layout(
+    report: &mut Report,
+    repo: &GitRepo,
+    config: &Config,
+    worktrees: &[Worktree],
+    yes: bool,
+) {
+    let current_path = std::env::current_dir().unwrap_or_default();
+    let store = MetadataStore::load(repo).unwrap_or_default();
+
+    // `pr` names the directory after the PR, not the branch `gh` checks out into it,
+    // and finds it again by that path, so it must stay put
+    let is_pr_worktree = |wt: &Worktree| {
+        let ticket_pr = store
+            .get(&wt.path)
+            .and_then(|meta| meta.ticket.as_deref())
+            .and_then(|ticket| ticket.strip_prefix('#'))
+            .is_some_and(|n| n.parse::<u32>().is_ok());
+        let dir_pr = wt
+            .path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .and_then(|name| name.rsplit_once("pr-"))
+            .and_then(|(_, n)| n.parse::<u32>().ok())
+            .is_some_and(|n| {
+                check_same_path(&wt.path, &config.worktree_path(repo, &format!("pr-{}", n)))
+            });
+        ticket_pr || dir_pr
+    };
+
+    let misplaced: Vec<(&Worktree, PathBuf)> = worktrees
+        .iter()
+        .filter(|wt| !wt.is_main_worktree(repo) && !wt.prunable && !is_pr_worktree(wt))
+        .filter_map(|wt| {
+            let branch = wt.branch_short.as_deref()?;
+            let expected = config.worktree_path(repo, branch);
+            if wt.path == expected || check_same_path(&wt.path, &expected) {
+                None
+            } else {
+                Some((wt, expected))
+            }
+        })
+        .collect();
+
+    if misplaced.is_empty() {
+        report.check(
+            "layout",
+            &format!("Worktrees match '{}' layout", config.layout),
+            true,
+        );
+        return;
+    }
+
+    report.add(
+        "layout",
+        CheckStatus::Warn,
+        format!(
+            "{} worktree(s) don't match the '{}' layout",
+            misplaced.len(),
+            config.layout
+        ),
+    );
+
+    for (wt, expected) in &misplaced {
+        report.info(&format!(
+            "{}: {} (expected {})",
+            wt.name(),
+            wt.path.display(),
+            expected.display()
+        ));
+    }
+
+    let confirmed = if yes {
+        true
+    } else if !report.json && std::io::stdin().is_terminal() {
+        Confirm::new()
+            .with_prompt(format!(
+                "Move {} worktree(s) to match the '{}' layout?",
+                misplaced.len(),
+                config.layout
+            ))
+            .default(false)
+            .interact()
+            .unwrap_or(false)
     } else {
-        eprintln!("{} {}", "✗".red(), name);
-        *all_ok = false;
+        report.hint("Run `git workty doctor --yes` to move them.".to_string());
+        false
+    };
+
+    if !confirmed {
+        return;
     }
-}
 
-fn print_fail(msg: &str) {
-    eprintln!("{} {}", "✗".red(), msg);
-}
+    let mut store = MetadataStore::load(repo).unwrap_or_default();
+    for (wt, expected) in &misplaced {
+        if wt.locked || wt.path == current_path {
+            report.info(&format!(
+                "{}: skipped ({})",
+                wt.name(),
+                if wt.locked {
+                    "locked"
+                } else {
+                    "current directory"
+                }
+            ));
+            continue;
+        }
+        if expected.exists() {
+            report.info(&format!(
+                "{}: skipped ({} already exists)",
+                wt.name(),
+                expected.display()
+            ));
+            continue;
+        }
 
-fn print_warn(msg: &str) {
-    eprintln!("{} {}", "!".yellow(), msg);
+        match repo.move_worktree(&wt.path, expected) {
+            Ok(()) => {
+                store.rename(&wt.path, expected);
+                report.info(&format!("{} Moved {}", "✓".green(), wt.name()));
+            }
+            Err(e) => report.add(
+                "layout_move",
+                CheckStatus::Fail,
+                format!("{}: {:#}", wt.name(), e),
+            ),
+        }
+    }
+
+    if let Err(e) = store.save() {
+        report.add(
+            "layout_move",
+            CheckStatus::Fail,
+            format!("Failed to save metadata: {:#}", e),
+        );
+    }
 }
@@ -1,9 +1,24 @@
+use crate::config::Config;
 use crate::git::GitRepo;
 use crate::ui;
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::process::Command;
 
-pub fn execute(repo: &GitRepo, all: bool) -> Result<()> {
+#[derive(Serialize)]
+struct JsonFetchResult<'a> {
+    remote: &'a str,
+    fetched: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonFetchOutput<'a> {
+    remotes: Vec<JsonFetchResult<'a>>,
+}
+
+pub fn execute(repo: &GitRepo, all: bool, json: bool) -> Result<()> {
+    let config = Config::load(repo)?;
     ui::print_info("Fetching from remotes...");
 
     let git_repo = repo.repo.lock().unwrap();
@@ -12,12 +27,13 @@ pub fn execute(repo: &GitRepo, all: bool) -> Result<()> {
     let remote_names: Vec<String> = if all {
         remotes.iter().flatten().map(|s| s.to_string()).collect()
     } else {
-        // Just fetch origin by default
-        vec!["origin".to_string()]
+        // Just the configured remote by default
+        vec![config.remote.clone()]
     };
 
     drop(git_repo); // Release the lock before running commands
 
+    let mut results = Vec::new();
     for remote in &remote_names {
         ui::print_info(&format!("  Fetching {}...", remote));
 
@@ -27,10 +43,22 @@ pub fn execute(repo: &GitRepo, all: bool) -> Result<()> {
             .output()
             .context("Failed to run git fetch")?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            ui::print_warning(&format!("Failed to fetch {}: {}", remote, stderr.trim()));
-        }
+        let error = if output.status.success() {
+            None
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            ui::print_warning(&format!("Failed to fetch {}: {}", remote, stderr));
+            Some(stderr)
+        };
+        results.push(JsonFetchResult {
+            remote,
+            fetched: error.is_none(),
+            error,
+        });
+    }
+
+    if json {
+        ui::print_json("fetch", &JsonFetchOutput { remotes: results });
     }
 
     ui::print_success(&format!(
@@ -1,17 +1,45 @@
+use crate::config::Config;
 use crate::git::GitRepo;
-use crate::worktree::{find_worktree, list_worktrees};
-use anyhow::{bail, Result};
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::ui;
+use crate::worktree::{list_worktrees, resolve_worktree};
+use anyhow::Result;
+use serde::Serialize;
 
-pub fn execute(repo: &GitRepo, name: &str) -> Result<()> {
+#[derive(Serialize)]
+struct JsonGoOutput<'a> {
+    name: &'a str,
+    branch: Option<&'a str>,
+    path: String,
+}
+
+pub fn execute(repo: &GitRepo, name: &str, json: bool) -> Result<()> {
     let worktrees = list_worktrees(repo)?;
 
-    if let Some(wt) = find_worktree(&worktrees, name) {
-        println!("{}", wt.path.display());
-        Ok(())
-    } else {
-        bail!(
-            "Worktree '{}' not found. Use `git workty list` to see available worktrees.",
-            name
+    let wt = resolve_worktree(&worktrees, name)?;
+    let config = Config::load(repo)?;
+    run_hook(
+        &config,
+        Hook::PostSwitch,
+        &HookContext {
+            worktree_path: &wt.path,
+            branch: wt.branch_short.as_deref(),
+            base: Some(&config.base),
+            repo_root: &repo.root,
+        },
+    )?;
+
+    if json {
+        ui::print_json(
+            "go",
+            &JsonGoOutput {
+                name: wt.name(),
+                branch: wt.branch_short.as_deref(),
+                path: wt.path.to_string_lossy().into_owned(),
+            },
         );
+    } else {
+        println!("{}", wt.path.display());
     }
+    Ok(())
 }
@@ -1,29 +1,54 @@
+use crate::config::Config;
+use crate::daemon;
 use crate::git::GitRepo;
+use crate::metadata::MetadataStore;
 use crate::status::WorktreeStatus;
-use crate::status::{get_all_statuses, get_all_statuses_fast};
+use crate::status::{get_all_statuses_fast, BaseRefs};
+use crate::status_cache::{cached_statuses, refresh_statuses, revalidate_in_background};
 use crate::ui::{print_worktree_list, UiOptions};
 use crate::worktree::{list_worktrees, Worktree};
 use anyhow::Result;
 use std::path::PathBuf;
 
-pub fn execute(repo: &GitRepo, opts: &UiOptions, fast: bool) -> Result<()> {
+pub fn execute(repo: &GitRepo, opts: &UiOptions, fast: bool, cached: bool) -> Result<()> {
+    let config = Config::load(repo)?;
     let worktrees = list_worktrees(repo)?;
+    let bases = BaseRefs::load(repo, &config, &worktrees);
     let statuses = if fast {
-        get_all_statuses_fast(repo, &worktrees)
+        get_all_statuses_fast(&worktrees, &bases)
+    } else if let Some(statuses) = daemon::query_statuses(repo, &worktrees) {
+        statuses
+    } else if cached {
+        let (statuses, stale) = cached_statuses(repo, &worktrees, &bases);
+        if stale {
+            revalidate_in_background(repo);
+        }
+        statuses
     } else {
-        get_all_statuses(repo, &worktrees)
+        refresh_statuses(repo, &worktrees, &bases)
     };
 
     let current_path = std::env::current_dir().unwrap_or_else(|_| PathBuf::new());
 
     let sorted = sort_worktrees(statuses, &current_path);
+    let metadata = MetadataStore::load(repo)?;
 
-    print_worktree_list(repo, &sorted, &current_path, opts);
+    print_worktree_list(repo, &sorted, &current_path, &metadata, opts);
 
     Ok(())
 }
 
-fn sort_worktrees(
+/// Recomputes and stores every status without printing anything; this is what
+/// `list --cached` runs in the background.
+pub fn refresh_cache(repo: &GitRepo) -> Result<()> {
+    let config = Config::load(repo)?;
+    let worktrees = list_worktrees(repo)?;
+    let bases = BaseRefs::load(repo, &config, &worktrees);
+    refresh_statuses(repo, &worktrees, &bases);
+    Ok(())
+}
+
+pub fn sort_worktrees(
     mut worktrees: Vec<(Worktree, WorktreeStatus)>,
     current_path: &PathBuf,
 ) -> Vec<(Worktree, WorktreeStatus)> {
@@ -0,0 +1,23 @@
+use crate::git::GitRepo;
+use crate::ui::print_success;
+use crate::worktree::{list_worktrees, resolve_worktree_exact};
+use anyhow::{bail, Result};
+
+pub fn execute(repo: &GitRepo, name: &str, reason: Option<&str>) -> Result<()> {
+    let worktrees = list_worktrees(repo)?;
+
+    let wt = resolve_worktree_exact(&worktrees, name)?;
+
+    if wt.is_main_worktree(repo) {
+        bail!("The main worktree cannot be locked");
+    }
+
+    if wt.locked {
+        bail!("Worktree '{}' is already locked", name);
+    }
+
+    repo.lock_worktree(&wt.path, reason)?;
+
+    print_success(&format!("Locked worktree '{}'", name));
+    Ok(())
+}
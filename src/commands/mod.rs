@@ -1,13 +1,23 @@
 pub mod clean;
 pub mod completions;
+pub mod config;
+pub mod dashboard;
 pub mod doctor;
 pub mod fetch;
 pub mod go;
 pub mod init;
 pub mod install_man;
 pub mod list;
+pub mod lock;
+pub mod mv;
 pub mod new;
+pub mod note;
 pub mod pick;
 pub mod pr;
+pub mod prompt;
+pub mod restore;
 pub mod rm;
 pub mod sync;
+pub mod undo;
+pub mod unlock;
+pub mod watch;
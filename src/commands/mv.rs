@@ -0,0 +1,118 @@
+use crate::config::Config;
+use crate::error::WorktyError;
+use crate::git::GitRepo;
+use crate::metadata::MetadataStore;
+use crate::status::worktree_operation;
+use crate::ui::{print_info, print_success, print_warning};
+use crate::worktree::{list_worktrees, resolve_worktree_exact, slug_from_branch};
+use anyhow::{bail, Context, Result};
+
+pub struct MvOptions {
+    pub old: String,
+    pub new: String,
+    /// Move even if a rebase, merge, etc. is in progress
+    pub force: bool,
+}
+
+pub fn execute(repo: &GitRepo, opts: MvOptions) -> Result<()> {
+    let config = Config::load(repo)?;
+    let worktrees = list_worktrees(repo)?;
+
+    let wt = resolve_worktree_exact(&worktrees, &opts.old)?;
+
+    let current_path = std::env::current_dir().context("Failed to get current directory")?;
+    if wt.path == current_path {
+        bail!("Cannot move the current worktree. Change to a different worktree first.");
+    }
+
+    if wt.is_main_worktree(repo) {
+        bail!("Cannot move the main worktree (original repository clone)");
+    }
+
+    if wt.locked {
+        return Err(WorktyError::Locked {
+            name: wt.name().to_string(),
+            reason: wt.lock_reason.clone(),
+        }
+        .into());
+    }
+
+    if let Some(operation) = worktree_operation(wt) {
+        if !opts.force {
+            return Err(WorktyError::InProgress {
+                name: wt.name().to_string(),
+                operation,
+            }
+            .into());
+        }
+        print_warning(&format!(
+            "Worktree '{}' is {} (--force specified)",
+            wt.name(),
+            operation.label()
+        ));
+    }
+
+    let old_branch = wt.branch_short.clone().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Worktree '{}' has a detached HEAD, so there is no branch to rename",
+            wt.name()
+        )
+    })?;
+
+    if repo.branch_exists(&opts.new) {
+        bail!("Branch '{}' already exists", opts.new);
+    }
+
+    let old_path = wt.path.clone();
+    let new_path = if old_path == config.worktree_path(repo, &old_branch) {
+        config.worktree_path(repo, &opts.new)
+    } else {
+        old_path
+            .parent()
+            .map(|parent| parent.join(slug_from_branch(&opts.new)))
+            .unwrap_or_else(|| config.worktree_path(repo, &opts.new))
+    };
+
+    if new_path != old_path && new_path.exists() {
+        bail!(
+            "Directory already exists: {}\nRemove it or pick a different name.",
+            new_path.display()
+        );
+    }
+
+    let mut store = MetadataStore::load(repo)?;
+
+    if new_path != old_path {
+        print_info(&format!(
+            "Moving {} -> {}",
+            old_path.display(),
+            new_path.display()
+        ));
+        repo.move_worktree(&old_path, &new_path)?;
+        store.rename(&old_path, &new_path);
+    }
+
+    if let Err(e) = repo.rename_branch(&old_branch, &opts.new) {
+        if new_path != old_path && repo.move_worktree(&new_path, &old_path).is_ok() {
+            store.rename(&new_path, &old_path);
+        } else if new_path != old_path {
+            print_warning(&format!(
+                "Worktree was moved to {} but the branch could not be renamed",
+                new_path.display()
+            ));
+        }
+        store.save()?;
+        return Err(e);
+    }
+
+    store.save()?;
+
+    print_success(&format!(
+        "Renamed '{}' to '{}' at {}",
+        old_branch,
+        opts.new,
+        new_path.display()
+    ));
+
+    Ok(())
+}
@@ -1,8 +1,13 @@
 use crate::config::Config;
-use crate::git::GitRepo;
-use crate::ui::{print_info, print_success};
+use crate::copy::seed_worktree;
+use crate::git::{GitRepo, WorktreeTarget};
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::metadata::{now_timestamp, MetadataStore};
+use crate::ui::{self, print_info, print_success};
 use crate::worktree::{list_worktrees, slug_from_branch};
 use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::process::Command;
 
@@ -14,17 +19,32 @@ pub struct NewOptions {
     pub open: bool,
     pub no_fetch: bool,
     pub no_push: bool,
+    pub no_copy: bool,
+    pub note: Option<String>,
+    pub ticket: Option<String>,
+    /// Value for the `{type}` placeholder in `branch_template`
+    pub branch_type: Option<String>,
+    /// Use the name verbatim instead of applying `branch_template`
+    pub no_template: bool,
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct JsonNewOutput<'a> {
+    branch: &'a str,
+    path: String,
+    base: &'a str,
+    created_branch: bool,
 }
 
 pub fn execute(repo: &GitRepo, opts: NewOptions) -> Result<()> {
     let config = Config::load(repo)?;
 
-    let branch_name = &opts.name;
-    let slug = slug_from_branch(branch_name);
+    let branch_name = &resolve_branch_name(repo, &config, &opts)?;
 
     let worktree_path = opts
         .path
-        .unwrap_or_else(|| config.worktree_path(repo, &slug));
+        .unwrap_or_else(|| config.worktree_path(repo, branch_name));
 
     if worktree_path.exists() {
         bail!(
@@ -48,30 +68,13 @@ pub fn execute(repo: &GitRepo, opts: NewOptions) -> Result<()> {
 
     let mut base = opts.from.unwrap_or_else(|| config.base.clone());
 
-    if let Some(parent) = worktree_path.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
-    }
-
     let branch_already_exists = repo.branch_exists(branch_name);
 
     if branch_already_exists {
         print_info(&format!("Using existing branch '{}'", branch_name));
 
-        let path_str = worktree_path
-            .to_str()
-            .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {:?}", worktree_path))?;
-
-        let output = Command::new("git")
-            .current_dir(&repo.root)
-            .args(["worktree", "add", path_str, branch_name])
-            .output()
+        repo.add_worktree(&worktree_path, WorktreeTarget::Branch(branch_name))
             .context("Failed to create worktree")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("Failed to create worktree: {}", stderr.trim());
-        }
     } else {
         // Try to fetch upstream of base to ensure we are up to date
         if !opts.no_fetch {
@@ -97,27 +100,21 @@ pub fn execute(repo: &GitRepo, opts: NewOptions) -> Result<()> {
             branch_name, base
         ));
 
-        let path_str = worktree_path
-            .to_str()
-            .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {:?}", worktree_path))?;
-
-        let output = Command::new("git")
-            .current_dir(&repo.root)
-            .args(["worktree", "add", "-b", branch_name, path_str, &base])
-            .output()
-            .context("Failed to create worktree")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("Failed to create worktree: {}", stderr.trim());
-        }
+        repo.add_worktree(
+            &worktree_path,
+            WorktreeTarget::NewBranch {
+                name: branch_name,
+                start_point: &base,
+            },
+        )
+        .context("Failed to create worktree")?;
 
         // Try to set upstream (unless --no-push)
         if !opts.no_push {
             print_info("Setting upstream...");
             let push_res = Command::new("git")
                 .current_dir(&repo.root)
-                .args(["push", "-u", "origin", branch_name])
+                .args(["push", "-u", &config.remote, branch_name])
                 .output();
 
             match push_res {
@@ -135,7 +132,52 @@ pub fn execute(repo: &GitRepo, opts: NewOptions) -> Result<()> {
         }
     }
 
-    if opts.print_path {
+    let mut store = MetadataStore::load(repo)?;
+    let meta = store.entry(&worktree_path);
+    meta.created_at = Some(now_timestamp());
+    meta.created_from = Some(base.clone());
+    meta.note = opts.note;
+    meta.ticket = opts.ticket;
+    store.save()?;
+
+    if !opts.no_copy {
+        let summary = seed_worktree(&config, &repo.main_worktree_path(), &worktree_path)?;
+        if summary.copied + summary.linked > 0 {
+            print_info(&format!(
+                "Copied {} and linked {} path(s) from the main worktree",
+                summary.copied, summary.linked
+            ));
+        }
+    }
+
+    run_hook(
+        &config,
+        Hook::PostCreate,
+        &HookContext {
+            worktree_path: &worktree_path,
+            branch: Some(branch_name),
+            base: Some(&base),
+            repo_root: &repo.root,
+        },
+    )
+    .with_context(|| {
+        format!(
+            "Worktree was created at {} but its post_create hook failed",
+            worktree_path.display()
+        )
+    })?;
+
+    if opts.json {
+        ui::print_json(
+            "new",
+            &JsonNewOutput {
+                branch: branch_name,
+                path: worktree_path.to_string_lossy().into_owned(),
+                base: &base,
+                created_branch: !branch_already_exists,
+            },
+        );
+    } else if opts.print_path {
         println!("{}", worktree_path.display());
     } else {
         print_success(&format!("Created worktree at {}", worktree_path.display()));
@@ -150,6 +192,109 @@ pub fn execute(repo: &GitRepo, opts: NewOptions) -> Result<()> {
     Ok(())
 }
 
+/// Applies `branch_template` and checks the result against `branch_pattern`.
+///
+/// Existing branches and names that already match the pattern are used as-is.
+fn resolve_branch_name(repo: &GitRepo, config: &Config, opts: &NewOptions) -> Result<String> {
+    if repo.branch_exists(&opts.name) {
+        return Ok(opts.name.clone());
+    }
+
+    let pattern = config
+        .branch_pattern
+        .as_deref()
+        .map(|p| {
+            Regex::new(&format!("^(?:{})$", p))
+                .with_context(|| format!("Invalid branch_pattern '{}'", p))
+        })
+        .transpose()?;
+
+    let already_valid = pattern.as_ref().is_some_and(|re| re.is_match(&opts.name));
+
+    let name = match &config.branch_template {
+        Some(template) if !already_valid && !opts.no_template => {
+            let user = repo
+                .config_value("user.name")
+                .map(|u| slug_from_branch(&u).to_lowercase());
+            expand_template(
+                template,
+                &opts.name,
+                user.as_deref(),
+                opts.branch_type.as_deref(),
+                opts.ticket.as_deref(),
+            )?
+        }
+        _ => opts.name.clone(),
+    };
+
+    if let Some(re) = &pattern {
+        if !re.is_match(&name) {
+            let mut msg = format!(
+                "Branch name '{}' doesn't match branch_pattern `{}`",
+                name,
+                re.as_str()
+            );
+            if let Some(template) = &config.branch_template {
+                msg.push_str(&format!(
+                    "\nExpected format: {}\nExample: git workty new login --type feat --ticket ABC-123",
+                    template
+                ));
+            }
+            bail!(msg);
+        }
+    }
+
+    Ok(name)
+}
+
+fn expand_template(
+    template: &str,
+    name: &str,
+    user: Option<&str>,
+    branch_type: Option<&str>,
+    ticket: Option<&str>,
+) -> Result<String> {
+    let placeholders = [
+        ("{name}", Some(name), "a name"),
+        ("{slug}", Some(name), "a name"),
+        ("{user}", user, "git config user.name"),
+        ("{type}", branch_type, "--type"),
+        ("{ticket}", ticket, "--ticket"),
+    ];
+
+    // One left-to-right pass, so a value containing `{...}` is never expanded again
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while !rest.is_empty() {
+        let Some((placeholder, value, source)) = placeholders
+            .iter()
+            .find(|(placeholder, _, _)| rest.starts_with(placeholder))
+        else {
+            let ch = rest.chars().next().unwrap_or_default();
+            expanded.push(ch);
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        };
+
+        let value = value.ok_or_else(|| {
+            anyhow::anyhow!(
+                "branch_template '{}' needs {} for {}",
+                template,
+                source,
+                placeholder
+            )
+        })?;
+        if *placeholder == "{slug}" {
+            expanded.push_str(&slug_from_branch(value).to_lowercase());
+        } else {
+            expanded.push_str(value);
+        }
+        rest = &rest[placeholder.len()..];
+    }
+
+    Ok(expanded)
+}
+
 fn get_upstream(repo: &GitRepo, branch: &str) -> Option<String> {
     let output = Command::new("git")
         .current_dir(&repo.root)
@@ -173,3 +318,34 @@ fn get_upstream(repo: &GitRepo, branch: &str) -> Option<String> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        let branch = expand_template(
+            "{user}/{type}/{ticket}-{slug}",
+            "Add Login",
+            Some("alice"),
+            Some("feat"),
+            Some("ABC-12"),
+        )
+        .unwrap();
+        assert_eq!(branch, "alice/feat/ABC-12-add-login");
+    }
+
+    #[test]
+    fn test_expand_template_does_not_reexpand_values() {
+        let branch =
+            expand_template("{type}/{name}", "{ticket}", None, Some("{name}"), None).unwrap();
+        assert_eq!(branch, "{name}/{ticket}");
+    }
+
+    #[test]
+    fn test_expand_template_missing_placeholder() {
+        let err = expand_template("{type}/{name}", "login", None, None, None).unwrap_err();
+        assert!(err.to_string().contains("--type"));
+    }
+}
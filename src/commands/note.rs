@@ -0,0 +1,60 @@
+use crate::git::GitRepo;
+use crate::metadata::MetadataStore;
+use crate::ui::print_success;
+use crate::worktree::{list_worktrees, resolve_worktree};
+use anyhow::Result;
+
+pub struct NoteOptions {
+    pub name: String,
+    pub text: Option<String>,
+    pub ticket: Option<String>,
+    /// Base branch to compare this worktree against instead of the configured one
+    pub base: Option<String>,
+    pub clear: bool,
+}
+
+pub fn execute(repo: &GitRepo, opts: NoteOptions) -> Result<()> {
+    let worktrees = list_worktrees(repo)?;
+
+    let wt = resolve_worktree(&worktrees, &opts.name)?;
+
+    let mut store = MetadataStore::load(repo)?;
+
+    if !opts.clear && opts.text.is_none() && opts.ticket.is_none() && opts.base.is_none() {
+        if let Some(meta) = store.get(&wt.path) {
+            if let Some(ticket) = &meta.ticket {
+                println!("[{}]", ticket);
+            }
+            if let Some(base) = &meta.base {
+                println!("base: {}", base);
+            }
+            if let Some(created_from) = &meta.created_from {
+                println!("created from: {}", created_from);
+            }
+            if let Some(note) = &meta.note {
+                println!("{}", note);
+            }
+        }
+        return Ok(());
+    }
+
+    let meta = store.entry(&wt.path);
+    if opts.clear {
+        meta.note = None;
+        meta.ticket = None;
+        meta.base = None;
+    }
+    if let Some(text) = opts.text {
+        meta.note = Some(text);
+    }
+    if let Some(ticket) = opts.ticket {
+        meta.ticket = Some(ticket);
+    }
+    if let Some(base) = opts.base {
+        meta.base = Some(base);
+    }
+    store.save()?;
+
+    print_success(&format!("Updated note for '{}'", wt.name()));
+    Ok(())
+}
@@ -1,5 +1,8 @@
+use crate::config::Config;
+use crate::daemon;
 use crate::git::GitRepo;
-use crate::status::get_all_statuses;
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::status::{get_all_statuses, BaseRefs};
 use crate::ui::{format_time, UiOptions};
 use crate::worktree::list_worktrees;
 use anyhow::{bail, Result};
@@ -17,8 +20,12 @@ pub fn execute(repo: &GitRepo, _opts: &UiOptions) -> Result<()> {
         bail!("No worktrees found");
     }
 
-    // Get status for richer display
-    let statuses = get_all_statuses(repo, &worktrees);
+    let config = Config::load(repo)?;
+
+    // Get status for richer display, from `git workty watch` if it is running
+    let bases = BaseRefs::load(repo, &config, &worktrees);
+    let statuses = daemon::query_statuses(repo, &worktrees)
+        .unwrap_or_else(|| get_all_statuses(&worktrees, &bases));
 
     // Find max name length for alignment
     let max_name_len = statuses
@@ -61,7 +68,19 @@ pub fn execute(repo: &GitRepo, _opts: &UiOptions) -> Result<()> {
 
     match selection {
         Some(idx) => {
-            println!("{}", statuses[idx].0.path.display());
+            let wt = &statuses[idx].0;
+            run_hook(
+                &config,
+                Hook::PostSwitch,
+                &HookContext {
+                    worktree_path: &wt.path,
+                    branch: wt.branch_short.as_deref(),
+                    base: Some(&config.base),
+                    repo_root: &repo.root,
+                },
+            )?;
+
+            println!("{}", wt.path.display());
             Ok(())
         }
         None => {
@@ -1,40 +1,69 @@
 use crate::config::Config;
+use crate::copy::seed_worktree;
+use crate::error::WorktyError;
 use crate::gh::{checkout_pr, get_pr_branch, is_gh_authenticated, is_gh_installed};
-use crate::git::GitRepo;
-use crate::ui::{print_info, print_success};
-use crate::worktree::{list_worktrees, slug_from_branch};
+use crate::git::{GitRepo, WorktreeTarget};
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::metadata::{now_timestamp, MetadataStore};
+use crate::status_cache;
+use crate::ui::{self, print_info, print_success};
+use crate::worktree::{check_same_path, list_worktrees};
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use std::process::Command;
 
 pub struct PrOptions {
     pub number: u32,
     pub print_path: bool,
     pub open: bool,
+    pub no_copy: bool,
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct JsonPrOutput<'a> {
+    number: u32,
+    branch: Option<&'a str>,
+    path: String,
+    /// The PR already had a worktree, so nothing was created
+    existing: bool,
 }
 
 pub fn execute(repo: &GitRepo, opts: PrOptions) -> Result<()> {
     if !is_gh_installed() {
-        bail!("GitHub CLI (gh) is not installed. Install it from https://cli.github.com/");
+        return Err(WorktyError::GhMissing.into());
     }
 
     if !is_gh_authenticated() {
-        bail!("GitHub CLI is not authenticated. Run `gh auth login` to authenticate.");
+        return Err(WorktyError::GhNotAuthenticated.into());
     }
 
     let config = Config::load(repo)?;
     let pr_name = format!("pr-{}", opts.number);
 
+    let pr_path = config.worktree_path(repo, &pr_name);
     let worktrees = list_worktrees(repo)?;
     if let Some(existing) = worktrees.iter().find(|wt| {
-        wt.branch_short.as_deref() == Some(&pr_name)
-            || wt.path.file_name().and_then(|s| s.to_str()) == Some(&pr_name)
+        wt.branch_short.as_deref() == Some(&pr_name) || check_same_path(&wt.path, &pr_path)
     }) {
         print_info(&format!(
             "PR #{} already has a worktree at {}",
             opts.number,
             existing.path.display()
         ));
-        println!("{}", existing.path.display());
+        if opts.json {
+            ui::print_json(
+                "pr",
+                &JsonPrOutput {
+                    number: opts.number,
+                    branch: existing.branch_short.as_deref(),
+                    path: existing.path.to_string_lossy().into_owned(),
+                    existing: true,
+                },
+            );
+        } else {
+            println!("{}", existing.path.display());
+        }
         return Ok(());
     }
 
@@ -44,8 +73,7 @@ pub fn execute(repo: &GitRepo, opts: PrOptions) -> Result<()> {
         opts.number, branch_name
     ));
 
-    let slug = slug_from_branch(&pr_name);
-    let worktree_path = config.worktree_path(repo, &slug);
+    let worktree_path = pr_path;
 
     if worktree_path.exists() {
         bail!(
@@ -54,29 +82,57 @@ pub fn execute(repo: &GitRepo, opts: PrOptions) -> Result<()> {
         );
     }
 
-    if let Some(parent) = worktree_path.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
-    }
-
-    let path_str = worktree_path
-        .to_str()
-        .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {:?}", worktree_path))?;
-
-    let output = Command::new("git")
-        .current_dir(&repo.root)
-        .args(["worktree", "add", path_str, "--detach"])
-        .output()
+    repo.add_worktree(&worktree_path, WorktreeTarget::Detached("HEAD"))
         .context("Failed to create worktree")?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to create worktree: {}", stderr.trim());
-    }
-
     checkout_pr(&worktree_path, opts.number)?;
+    status_cache::invalidate(repo, &[&worktree_path]);
+
+    let mut store = MetadataStore::load(repo)?;
+    let meta = store.entry(&worktree_path);
+    meta.created_at = Some(now_timestamp());
+    meta.created_from = Some(config.base.clone());
+    meta.ticket = Some(format!("#{}", opts.number));
+    store.save()?;
+
+    if !opts.no_copy {
+        let summary = seed_worktree(&config, &repo.main_worktree_path(), &worktree_path)?;
+        if summary.copied + summary.linked > 0 {
+            print_info(&format!(
+                "Copied {} and linked {} path(s) from the main worktree",
+                summary.copied, summary.linked
+            ));
+        }
+    }
 
-    if opts.print_path {
+    run_hook(
+        &config,
+        Hook::PostCreate,
+        &HookContext {
+            worktree_path: &worktree_path,
+            branch: Some(&branch_name),
+            base: Some(&config.base),
+            repo_root: &repo.root,
+        },
+    )
+    .with_context(|| {
+        format!(
+            "Worktree was created at {} but its post_create hook failed",
+            worktree_path.display()
+        )
+    })?;
+
+    if opts.json {
+        ui::print_json(
+            "pr",
+            &JsonPrOutput {
+                number: opts.number,
+                branch: Some(&branch_name),
+                path: worktree_path.to_string_lossy().into_owned(),
+                existing: false,
+            },
+        );
+    } else if opts.print_path {
         println!("{}", worktree_path.display());
     } else {
         print_success(&format!(
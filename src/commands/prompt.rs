@@ -0,0 +1,168 @@
+use crate::config::Config;
+use crate::daemon;
+use crate::git::GitRepo;
+use crate::status::{get_all_statuses_fast, get_worktree_status, BaseRefs, WorktreeStatus};
+use crate::ui::{Icons, UiOptions};
+use crate::worktree::{check_same_path, list_worktrees};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_FORMAT: &str = "{name}{dirty} {ahead}{behind}{rebase}";
+pub const DEFAULT_TIMEOUT_MS: u64 = 200;
+
+pub struct PromptOptions {
+    pub format: String,
+    /// Hard limit on the whole command; whatever status is known by then is printed
+    pub timeout: Duration,
+}
+
+/// What the status thread has managed to compute so far.
+struct Partial {
+    name: String,
+    /// None until the ahead/behind counts are known
+    status: Option<WorktreeStatus>,
+    /// False until the dirty check has run
+    complete: bool,
+}
+
+/// Prints the segment for the worktree containing `start_path`, or nothing at all
+/// outside a repository. Never fails, so a broken repo can't break the prompt.
+pub fn execute(start_path: Option<&Path>, opts: &PromptOptions, ui_opts: &UiOptions) {
+    let deadline = Instant::now() + opts.timeout;
+
+    let (tx, rx) = mpsc::channel();
+    let start_path = start_path.map(Path::to_path_buf);
+    // Everything that touches the repository happens here, under the deadline. Left
+    // running if it overshoots the budget; the process exits without waiting.
+    std::thread::spawn(move || {
+        let Ok(repo) = GitRepo::discover(start_path.as_deref()) else {
+            return;
+        };
+        let Some(worktree) = list_worktrees(&repo).ok().and_then(|wts| {
+            wts.into_iter()
+                .find(|wt| check_same_path(&wt.path, &repo.root))
+        }) else {
+            return;
+        };
+        let name = worktree.name().to_string();
+        let send = |status, complete| {
+            let _ = tx.send(Partial {
+                name: name.clone(),
+                status,
+                complete,
+            });
+        };
+        send(None, false);
+
+        let worktrees = std::slice::from_ref(&worktree);
+        if let Some(mut statuses) = daemon::query_statuses(&repo, worktrees) {
+            let (_, status) = statuses.remove(0);
+            send(Some(status), true);
+            return;
+        }
+
+        let config = Config::load(&repo).unwrap_or_default();
+        let bases = BaseRefs::load(&repo, &config, worktrees);
+        if let Some((_, status)) = get_all_statuses_fast(worktrees, &bases).pop() {
+            send(Some(status), false);
+        }
+        send(
+            Some(get_worktree_status(&worktree, bases.get(&worktree.path))),
+            true,
+        );
+    });
+
+    let mut best = None;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(remaining) {
+            Ok(partial) => {
+                let complete = partial.complete;
+                best = Some(partial);
+                if complete {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    let Some(best) = best else {
+        return;
+    };
+    let segment = render(&opts.format, &best, &Icons::from_options(ui_opts));
+    if !segment.is_empty() {
+        println!("{}", segment);
+    }
+}
+
+/// Fills in `{name}`, `{dirty}`, `{ahead}`, `{behind}` and `{rebase}`. Each one
+/// except `{name}` is empty when there's nothing to report, or nothing known yet.
+fn render(format: &str, partial: &Partial, icons: &Icons) -> String {
+    let count = |icon: &str, n: Option<usize>| match n {
+        Some(n) if n > 0 => format!("{}{}", icon, n),
+        _ => String::new(),
+    };
+
+    let (dirty, ahead, behind, rebase) = match &partial.status {
+        Some(status) => (
+            if partial.complete && status.is_dirty() {
+                icons.dirty.to_string()
+            } else {
+                String::new()
+            },
+            count(icons.arrow_up, status.ahead),
+            count(icons.arrow_down, status.behind),
+            count(icons.rebase, status.behind_main),
+        ),
+        None => Default::default(),
+    };
+
+    format
+        .replace("{name}", &partial.name)
+        .replace("{dirty}", &dirty)
+        .replace("{ahead}", &ahead)
+        .replace("{behind}", &behind)
+        .replace("{rebase}", &rebase)
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_skips_empty_placeholders() {
+        let icons = Icons::ascii();
+        let status = WorktreeStatus {
+            dirty_count: 2,
+            ahead: Some(1),
+            behind: Some(0),
+            behind_main: Some(3),
+            ..Default::default()
+        };
+
+        let complete = Partial {
+            name: "feat".to_string(),
+            status: Some(status.clone()),
+            complete: true,
+        };
+        assert_eq!(render(DEFAULT_FORMAT, &complete, &icons), "feat* ^1R3");
+
+        // Without the dirty check, the marker is left out rather than guessed
+        let partial = Partial {
+            name: "feat".to_string(),
+            status: Some(status),
+            complete: false,
+        };
+        assert_eq!(render("[{name}{dirty}]", &partial, &icons), "[feat]");
+
+        let name_only = Partial {
+            name: "feat".to_string(),
+            status: None,
+            complete: false,
+        };
+        assert_eq!(render(DEFAULT_FORMAT, &name_only, &icons), "feat");
+    }
+}
@@ -0,0 +1,86 @@
+use crate::archive::{find_archive, list_archives, restore_archive, ArchiveManifest};
+use crate::config::Config;
+use crate::git::GitRepo;
+use crate::metadata::{now_timestamp, MetadataStore};
+use crate::ui::{format_time, print_info, print_success, shorten_path};
+use anyhow::{bail, Result};
+
+pub fn execute(repo: &GitRepo, archive: Option<&str>) -> Result<()> {
+    let name = match archive {
+        Some(name) => name,
+        None => return print_archives(repo),
+    };
+
+    let config = Config::load(repo)?;
+    let dir = find_archive(repo, name)?;
+    let manifest = ArchiveManifest::load(&dir)?;
+
+    // Prefer the original location, then wherever the layout would put it today
+    let path = if !manifest.path.exists() {
+        manifest.path.clone()
+    } else {
+        let layout_path =
+            config.worktree_path(repo, manifest.branch.as_deref().unwrap_or(&manifest.name));
+        if layout_path.exists() {
+            bail!(
+                "Both {} and {} already exist. Remove one before restoring.",
+                manifest.path.display(),
+                layout_path.display()
+            );
+        }
+        layout_path
+    };
+
+    restore_archive(repo, &dir, &manifest, &path)?;
+
+    if let Some(meta) = &manifest.metadata {
+        let mut store = MetadataStore::load(repo)?;
+        *store.entry(&path) = meta.clone();
+        store.save()?;
+    }
+
+    print_success(&format!(
+        "Restored '{}' at {}",
+        manifest.name,
+        shorten_path(&path)
+    ));
+    print_info(&format!(
+        "The archive is kept in {}; delete it once you no longer need it.",
+        shorten_path(&dir)
+    ));
+
+    Ok(())
+}
+
+fn print_archives(repo: &GitRepo) -> Result<()> {
+    let archives = list_archives(repo)?;
+    if archives.is_empty() {
+        print_info("No archived worktrees. Use `git workty rm --archive` to create one.");
+        return Ok(());
+    }
+
+    let now = now_timestamp();
+    for (dir, manifest) in archives.iter().rev() {
+        let mut saved = Vec::new();
+        if manifest.has_bundle {
+            saved.push("commits");
+        }
+        if manifest.has_patch {
+            saved.push("changes");
+        }
+        let saved = if saved.is_empty() {
+            "nothing unpushed".to_string()
+        } else {
+            saved.join(" + ")
+        };
+
+        println!(
+            "{}  {:>5}  {}",
+            dir.file_name().unwrap_or_default().to_string_lossy(),
+            format_time(Some(now - manifest.created_at)),
+            saved
+        );
+    }
+
+    Ok(())
+}
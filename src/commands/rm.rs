@@ -1,28 +1,41 @@
+use crate::archive::archive_worktree;
+use crate::config::Config;
+use crate::error::WorktyError;
 use crate::git::GitRepo;
-use crate::status::is_worktree_dirty;
-use crate::ui::{print_success, print_warning};
-use crate::worktree::{find_worktree, list_worktrees};
+use crate::hooks::{run_hook, Hook, HookContext};
+use crate::metadata::MetadataStore;
+use crate::oplog::{record_removals, RemovedWorktree};
+use crate::status::{is_worktree_dirty, worktree_operation};
+use crate::ui::{self, print_info, print_success, print_warning, shorten_path, JsonRemoved};
+use crate::worktree::{list_worktrees, resolve_worktree_exact};
 use anyhow::{bail, Context, Result};
 use dialoguer::Confirm;
 use is_terminal::IsTerminal;
-use std::process::Command;
+use serde::Serialize;
 
 pub struct RmOptions {
     pub name: String,
-    pub force: bool,
+    /// Number of times --force was given; twice also removes locked worktrees
+    pub force: u8,
     pub delete_branch: bool,
     pub yes: bool,
+    /// Overrides the `archive` config default
+    pub archive: Option<bool>,
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct JsonRmOutput {
+    removed: Vec<JsonRemoved>,
+    deleted_branch: bool,
 }
 
 pub fn execute(repo: &GitRepo, opts: RmOptions) -> Result<()> {
+    let config = Config::load(repo)?;
     let worktrees = list_worktrees(repo)?;
 
-    let wt = find_worktree(&worktrees, &opts.name).ok_or_else(|| {
-        anyhow::anyhow!(
-            "Worktree '{}' not found. Use `git workty list` to see available worktrees.",
-            opts.name
-        )
-    })?;
+    let wt = resolve_worktree_exact(&worktrees, &opts.name)?;
+    let name = wt.name();
 
     let current_path = std::env::current_dir().context("Failed to get current directory")?;
     if wt.path == current_path {
@@ -33,18 +46,41 @@ pub fn execute(repo: &GitRepo, opts: RmOptions) -> Result<()> {
         bail!("Cannot remove the main worktree (original repository clone)");
     }
 
+    if wt.locked && opts.force < 2 {
+        return Err(WorktyError::Locked {
+            name: name.to_string(),
+            reason: wt.lock_reason.clone(),
+        }
+        .into());
+    }
+
+    if let Some(operation) = worktree_operation(wt) {
+        if opts.force == 0 {
+            return Err(WorktyError::InProgress {
+                name: name.to_string(),
+                operation,
+            }
+            .into());
+        }
+        print_warning(&format!(
+            "Worktree '{}' is {} (--force specified)",
+            name,
+            operation.label()
+        ));
+    }
+
     let is_dirty = is_worktree_dirty(wt);
-    if is_dirty && !opts.force {
-        bail!(
-            "Worktree '{}' has uncommitted changes. Use --force to remove anyway.",
-            opts.name
-        );
+    if is_dirty && opts.force == 0 {
+        return Err(WorktyError::Dirty {
+            name: name.to_string(),
+        }
+        .into());
     }
 
     if is_dirty {
         print_warning(&format!(
             "Worktree '{}' has uncommitted changes (--force specified)",
-            opts.name
+            name
         ));
     }
 
@@ -52,7 +88,7 @@ pub fn execute(repo: &GitRepo, opts: RmOptions) -> Result<()> {
         let confirm = Confirm::new()
             .with_prompt(format!(
                 "Remove worktree '{}'{}?",
-                opts.name,
+                name,
                 if opts.delete_branch {
                     " and its branch"
                 } else {
@@ -63,61 +99,77 @@ pub fn execute(repo: &GitRepo, opts: RmOptions) -> Result<()> {
             .interact()?;
 
         if !confirm {
-            bail!("Aborted");
+            return Err(WorktyError::Declined.into());
         }
     }
 
+    run_hook(
+        &config,
+        Hook::PreRemove,
+        &HookContext {
+            worktree_path: &wt.path,
+            branch: wt.branch_short.as_deref(),
+            base: Some(&config.base),
+            repo_root: &repo.root,
+        },
+    )
+    .context("pre_remove hook failed, worktree was not removed")?;
+
     let branch_name = wt.branch_short.clone();
     let wt_path = wt.path.clone();
-    let path_str = wt_path
-        .to_str()
-        .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {:?}", wt_path))?;
 
-    let mut args = vec!["worktree", "remove"];
-    if opts.force {
-        args.push("--force");
+    // Resolve the metadata key while the directory still exists
+    let mut store = MetadataStore::load(repo)?;
+
+    let mut archive_dir = None;
+    if opts.archive.unwrap_or(config.archive) {
+        let dir = archive_worktree(repo, wt, &config.base, store.get(&wt_path))
+            .context("Failed to archive worktree, it was not removed")?;
+        print_info(&format!("Archived '{}' to {}", name, shorten_path(&dir)));
+        archive_dir = Some(dir);
     }
-    args.push(path_str);
 
-    let output = Command::new("git")
-        .current_dir(&repo.root)
-        .args(&args)
-        .output()
-        .context("Failed to remove worktree")?;
+    let record = RemovedWorktree::capture(repo, wt, store.get(&wt_path));
+    store.remove(&wt_path);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to remove worktree: {}", stderr.trim());
-    }
+    repo.remove_worktree(&wt_path, opts.force)
+        .context("Failed to remove worktree")?;
 
-    print_success(&format!("Removed worktree '{}'", opts.name));
+    store.save()?;
+    record_removals(repo, "rm", vec![record]);
+    print_success(&format!("Removed worktree '{}'", name));
+    print_info("Run `git workty undo` to bring it back.");
 
+    let mut deleted_branch = false;
     if opts.delete_branch {
-        if let Some(branch) = branch_name {
-            let output = Command::new("git")
-                .current_dir(&repo.root)
-                .args(["branch", "-d", &branch])
-                .output();
-
-            match output {
-                Ok(o) if o.status.success() => {
+        if let Some(branch) = &branch_name {
+            match repo.run_git(&["branch", "-d", branch]) {
+                Ok(_) => {
                     print_success(&format!("Deleted branch '{}'", branch));
-                }
-                Ok(o) => {
-                    let stderr = String::from_utf8_lossy(&o.stderr);
-                    print_warning(&format!(
-                        "Could not delete branch '{}': {}",
-                        branch,
-                        stderr.trim()
-                    ));
-                    eprintln!("Hint: Use `git branch -D {}` to force delete.", branch);
+                    deleted_branch = true;
                 }
                 Err(e) => {
-                    print_warning(&format!("Could not delete branch '{}': {}", branch, e));
+                    print_warning(&format!("Could not delete branch '{}': {:#}", branch, e));
+                    eprintln!("Hint: Use `git branch -D {}` to force delete.", branch);
                 }
             }
         }
     }
 
+    if opts.json {
+        ui::print_json(
+            "rm",
+            &JsonRmOutput {
+                removed: vec![JsonRemoved {
+                    name: name.to_string(),
+                    path: wt_path.to_string_lossy().into_owned(),
+                    branch: branch_name,
+                    archive: archive_dir.map(|d| d.to_string_lossy().into_owned()),
+                }],
+                deleted_branch,
+            },
+        );
+    }
+
     Ok(())
 }
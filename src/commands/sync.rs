@@ -1,37 +1,134 @@
+use crate::config::Config;
 use crate::git::GitRepo;
-use crate::status::{get_all_statuses, is_worktree_dirty};
+use crate::status::{get_all_statuses, is_worktree_dirty, worktree_operation, BaseRefs, Operation};
+use crate::status_cache;
 use crate::ui;
-use crate::worktree::list_worktrees;
+use crate::worktree::{list_worktrees, Worktree};
 use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::Path;
 use std::process::Command;
 
+/// Worktrees synced at once when `--jobs` isn't given.
+const DEFAULT_JOBS: usize = 4;
+
+/// How a worktree's branch is brought up to date.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncStrategy {
+    #[default]
+    Rebase,
+    Merge,
+    /// Only fast-forward; branches with local commits are left alone
+    FfOnly,
+}
+
 pub struct SyncOptions {
     pub dry_run: bool,
     pub fetch: bool,
+    pub json: bool,
+    pub strategy: SyncStrategy,
+    /// Sync onto the configured base instead of each branch's upstream
+    pub onto_base: bool,
+    pub jobs: Option<usize>,
+    /// Try worktrees with a rebase, merge, etc. in progress instead of skipping them.
+    /// They are reported as failed, and the user's operation is never aborted.
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SyncOutcome {
+    UpToDate,
+    WouldRebase,
+    WouldMerge,
+    WouldFastForward,
+    Rebased,
+    Merged,
+    FastForwarded,
+    /// Stopped on conflicts and aborted, leaving the branch as it was
+    Conflicted,
+    Failed,
+    SkippedDirty,
+    SkippedInProgress,
+    SkippedNoUpstream,
+    SkippedNoBase,
+}
+
+impl SyncOutcome {
+    fn label(self) -> &'static str {
+        match self {
+            Self::UpToDate => "up to date",
+            Self::WouldRebase => "would rebase",
+            Self::WouldMerge => "would merge",
+            Self::WouldFastForward => "would fast-forward",
+            Self::Rebased => "rebased",
+            Self::Merged => "merged",
+            Self::FastForwarded => "fast-forwarded",
+            Self::Conflicted => "conflicts, aborted",
+            Self::Failed => "failed",
+            Self::SkippedDirty => "skipped (dirty)",
+            Self::SkippedInProgress => "skipped (operation in progress)",
+            Self::SkippedNoUpstream => "skipped (no upstream)",
+            Self::SkippedNoBase => "skipped (no base)",
+        }
+    }
+
+    fn is_skip(self) -> bool {
+        matches!(
+            self,
+            Self::SkippedDirty
+                | Self::SkippedInProgress
+                | Self::SkippedNoUpstream
+                | Self::SkippedNoBase
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSyncResult {
+    name: String,
+    path: String,
+    outcome: SyncOutcome,
+    behind: Option<usize>,
+    /// Ref the worktree was synced onto
+    onto: Option<String>,
+    conflicts: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonSyncOutput {
+    dry_run: bool,
+    strategy: SyncStrategy,
+    onto_base: bool,
+    results: Vec<JsonSyncResult>,
 }
 
 pub fn execute(repo: &GitRepo, opts: SyncOptions) -> Result<()> {
+    let config = Config::load(repo)?;
+
     // Optionally fetch first
     if opts.fetch {
-        ui::print_info("Fetching from origin...");
+        ui::print_info(&format!("Fetching from {}...", config.remote));
         let output = Command::new("git")
             .current_dir(&repo.root)
-            .args(["fetch", "--prune", "origin"])
+            .args(["fetch", "--prune", &config.remote])
             .output()
             .context("Failed to fetch")?;
 
         if !output.status.success() {
-            ui::print_warning("Failed to fetch from origin");
+            ui::print_warning(&format!("Failed to fetch from {}", config.remote));
         }
     }
 
     let worktrees = list_worktrees(repo)?;
-    let statuses = get_all_statuses(repo, &worktrees);
+    let bases = BaseRefs::load(repo, &config, &worktrees);
+    let statuses = get_all_statuses(&worktrees, &bases);
 
-    let mut synced = 0;
-    let mut skipped_dirty = 0;
-    let mut skipped_no_upstream = 0;
-    let mut failed = 0;
+    let mut results = Vec::new();
+    let mut pending = Vec::new();
 
     for (wt, status) in &statuses {
         // Skip main worktree (usually you don't want to auto-rebase main)
@@ -39,77 +136,274 @@ pub fn execute(repo: &GitRepo, opts: SyncOptions) -> Result<()> {
             continue;
         }
 
-        // Skip detached HEAD
-        if wt.detached {
+        // Skip detached HEAD, unless it's a rebase in progress that should be reported
+        if wt.detached && status.operation.is_none() {
             continue;
         }
 
-        // Skip if no upstream
-        if status.upstream.is_none() {
-            skipped_no_upstream += 1;
-            continue;
-        }
-
-        // Skip if nothing to sync
-        let behind = status.behind.unwrap_or(0);
-        if behind == 0 {
-            continue;
-        }
+        let (onto, behind) = if opts.onto_base {
+            (
+                bases.get(&wt.path).map(|base| base.name()),
+                status.behind_main,
+            )
+        } else {
+            (status.upstream.clone(), status.behind)
+        };
 
-        let branch_name = wt.name();
+        let mut result = JsonSyncResult {
+            name: wt.name().to_string(),
+            path: wt.path.to_string_lossy().into_owned(),
+            outcome: SyncOutcome::UpToDate,
+            behind,
+            onto,
+            conflicts: Vec::new(),
+            error: None,
+        };
 
-        // Skip if dirty
-        if is_worktree_dirty(wt) {
-            if !opts.dry_run {
-                ui::print_warning(&format!("{}: skipped (dirty)", branch_name));
+        // A half-finished rebase or merge isn't "up to date", whatever the counts say
+        if let Some(operation) = status.operation {
+            if opts.force {
+                let run = in_progress_failure(operation);
+                result.outcome = run.outcome;
+                result.error = run.error;
+            } else {
+                result.outcome = SyncOutcome::SkippedInProgress;
             }
-            skipped_dirty += 1;
-            continue;
+        } else if result.onto.is_none() {
+            result.outcome = if opts.onto_base {
+                SyncOutcome::SkippedNoBase
+            } else {
+                SyncOutcome::SkippedNoUpstream
+            };
+        } else if behind.unwrap_or(0) == 0 {
+            result.outcome = SyncOutcome::UpToDate;
+        } else if is_worktree_dirty(wt) {
+            result.outcome = SyncOutcome::SkippedDirty;
+        } else if opts.dry_run {
+            result.outcome = match opts.strategy {
+                SyncStrategy::Rebase => SyncOutcome::WouldRebase,
+                SyncStrategy::Merge => SyncOutcome::WouldMerge,
+                SyncStrategy::FfOnly => SyncOutcome::WouldFastForward,
+            };
+        } else {
+            pending.push((wt, results.len()));
         }
+        results.push(result);
+    }
 
-        if opts.dry_run {
-            ui::print_info(&format!(
-                "{}: would rebase ({} commits behind)",
-                branch_name, behind
-            ));
-            synced += 1;
-            continue;
-        }
+    if !pending.is_empty() {
+        let jobs = opts.jobs.unwrap_or(DEFAULT_JOBS).max(1);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .context("Failed to start sync workers")?;
 
-        // Perform the rebase
-        ui::print_info(&format!("{}: rebasing...", branch_name));
+        // Worktrees have their own index and HEAD, so they can be synced independently
+        let synced: Vec<(usize, SyncRun)> = pool.install(|| {
+            pending
+                .par_iter()
+                .map(|(wt, idx)| {
+                    let onto = results[*idx].onto.as_deref().unwrap_or_default();
+                    (*idx, sync_worktree(wt, onto, opts.strategy))
+                })
+                .collect()
+        });
 
-        let output = Command::new("git")
-            .current_dir(&wt.path)
-            .args(["rebase"])
-            .output()
-            .context("Failed to run git rebase")?;
+        let synced_paths: Vec<&Path> = pending.iter().map(|(wt, _)| wt.path.as_path()).collect();
+        status_cache::invalidate(repo, &synced_paths);
 
-        if output.status.success() {
-            ui::print_success(&format!("{}: rebased", branch_name));
-            synced += 1;
-        } else {
-            // Abort the failed rebase
-            let _ = Command::new("git")
-                .current_dir(&wt.path)
-                .args(["rebase", "--abort"])
-                .output();
-
-            ui::print_warning(&format!("{}: rebase failed, aborted", branch_name));
-            failed += 1;
+        for (idx, run) in synced {
+            let result = &mut results[idx];
+            result.outcome = run.outcome;
+            result.conflicts = run.conflicts;
+            result.error = run.error;
         }
     }
 
+    if opts.json {
+        ui::print_json(
+            "sync",
+            &JsonSyncOutput {
+                dry_run: opts.dry_run,
+                strategy: opts.strategy,
+                onto_base: opts.onto_base,
+                results,
+            },
+        );
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        ui::print_info("No worktrees to sync.");
+        return Ok(());
+    }
+
+    print_results_table(&results);
+
     // Summary
-    println!();
+    let count = |f: fn(SyncOutcome) -> bool| results.iter().filter(|r| f(r.outcome)).count();
+    eprintln!();
     if opts.dry_run {
-        ui::print_info(&format!("Would sync {} worktree(s)", synced));
+        ui::print_info(&format!(
+            "Would sync {} worktree(s)",
+            count(|o| matches!(
+                o,
+                SyncOutcome::WouldRebase | SyncOutcome::WouldMerge | SyncOutcome::WouldFastForward
+            ))
+        ));
     } else {
         ui::print_info(&format!(
-            "Synced: {}, Skipped (dirty): {}, Skipped (no upstream): {}, Failed: {}",
-            synced, skipped_dirty, skipped_no_upstream, failed
+            "Synced: {}, Up to date: {}, Skipped: {}, Failed: {}",
+            count(|o| matches!(
+                o,
+                SyncOutcome::Rebased | SyncOutcome::Merged | SyncOutcome::FastForwarded
+            )),
+            count(|o| o == SyncOutcome::UpToDate),
+            count(SyncOutcome::is_skip),
+            count(|o| matches!(o, SyncOutcome::Conflicted | SyncOutcome::Failed)),
         ));
     }
 
     Ok(())
 }
+
+struct SyncRun {
+    outcome: SyncOutcome,
+    conflicts: Vec<String>,
+    error: Option<String>,
+}
+
+fn sync_worktree(wt: &Worktree, onto: &str, strategy: SyncStrategy) -> SyncRun {
+    // Git would refuse to start anyway, and aborting afterwards would throw away
+    // the user's own operation and conflict resolutions
+    if let Some(operation) = worktree_operation(wt) {
+        return in_progress_failure(operation);
+    }
+
+    let (args, success, abort): (&[&str], SyncOutcome, Option<&[&str]>) = match strategy {
+        SyncStrategy::Rebase => (
+            &["rebase", onto],
+            SyncOutcome::Rebased,
+            Some(&["rebase", "--abort"]),
+        ),
+        SyncStrategy::Merge => (
+            &["merge", "--no-edit", onto],
+            SyncOutcome::Merged,
+            Some(&["merge", "--abort"]),
+        ),
+        SyncStrategy::FfOnly => (
+            &["merge", "--ff-only", onto],
+            SyncOutcome::FastForwarded,
+            None,
+        ),
+    };
+
+    let output = match Command::new("git")
+        .current_dir(&wt.path)
+        .args(args)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            return SyncRun {
+                outcome: SyncOutcome::Failed,
+                conflicts: Vec::new(),
+                error: Some(format!("Failed to run git {}: {}", args[0], e)),
+            }
+        }
+    };
+
+    if output.status.success() {
+        return SyncRun {
+            outcome: success,
+            conflicts: Vec::new(),
+            error: None,
+        };
+    }
+
+    // Nothing was in progress before, so whatever is now was started by this sync.
+    // Read the conflicts before aborting throws them away.
+    let conflicts = conflicted_files(&wt.path);
+    if let Some(abort) = abort {
+        let _ = Command::new("git")
+            .current_dir(&wt.path)
+            .args(abort)
+            .output();
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    SyncRun {
+        outcome: if conflicts.is_empty() {
+            SyncOutcome::Failed
+        } else {
+            SyncOutcome::Conflicted
+        },
+        conflicts,
+        error: (!stderr.is_empty()).then_some(stderr),
+    }
+}
+
+fn in_progress_failure(operation: Operation) -> SyncRun {
+    SyncRun {
+        outcome: SyncOutcome::Failed,
+        conflicts: Vec::new(),
+        error: Some(format!(
+            "Already {}; finish it or run `{}` first",
+            operation.label(),
+            operation.abort_command()
+        )),
+    }
+}
+
+fn conflicted_files(path: &Path) -> Vec<String> {
+    Command::new("git")
+        .current_dir(path)
+        .args(["diff", "--name-only", "--diff-filter=U"])
+        .output()
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Goes to stderr with the rest of the human-readable report; `--json` is the
+/// machine-readable output.
+fn print_results_table(results: &[JsonSyncResult]) {
+    let name_width = results
+        .iter()
+        .map(|r| r.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("WORKTREE".len());
+    let result_width = results
+        .iter()
+        .map(|r| r.outcome.label().len())
+        .max()
+        .unwrap_or(0)
+        .max("RESULT".len());
+
+    eprintln!(
+        "{:name_width$}  {:result_width$}  ONTO",
+        "WORKTREE", "RESULT"
+    );
+    for r in results {
+        eprintln!(
+            "{:name_width$}  {:result_width$}  {}",
+            r.name,
+            r.outcome.label(),
+            r.onto.as_deref().unwrap_or("-")
+        );
+        if !r.conflicts.is_empty() {
+            eprintln!("    conflicts: {}", r.conflicts.join(", "));
+        } else if let Some(error) = r
+            .error
+            .as_deref()
+            .filter(|_| r.outcome == SyncOutcome::Failed)
+        {
+            eprintln!("    {}", error.lines().next().unwrap_or_default());
+        }
+    }
+}
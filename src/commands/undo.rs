@@ -0,0 +1,77 @@
+use crate::git::GitRepo;
+use crate::metadata::MetadataStore;
+use crate::oplog::{OpLog, Operation};
+use crate::ui::{print_info, print_success, print_warning, shorten_path};
+use anyhow::Result;
+
+pub fn execute(repo: &GitRepo) -> Result<()> {
+    let mut log = OpLog::load(repo)?;
+
+    let operation = match log.pop() {
+        Some(op) => op,
+        None => {
+            print_info("Nothing to undo.");
+            return Ok(());
+        }
+    };
+
+    let mut store = MetadataStore::load(repo)?;
+    let mut failed = Vec::new();
+
+    for removed in operation.removed {
+        if removed.path.exists() {
+            print_warning(&format!(
+                "Could not restore '{}': {} already exists",
+                removed.name,
+                removed.path.display()
+            ));
+            failed.push(removed);
+            continue;
+        }
+
+        let result = repo.recreate_worktree(
+            &removed.path,
+            removed.branch.as_deref(),
+            &removed.head,
+            removed.upstream.as_ref(),
+        );
+        if let Err(e) = result {
+            print_warning(&format!("Could not restore '{}': {:#}", removed.name, e));
+            failed.push(removed);
+            continue;
+        }
+
+        if removed.locked {
+            if let Err(e) = repo.lock_worktree(&removed.path, removed.lock_reason.as_deref()) {
+                print_warning(&format!("Could not re-lock '{}': {:#}", removed.name, e));
+            }
+        }
+        if let Some(meta) = &removed.metadata {
+            *store.entry(&removed.path) = meta.clone();
+        }
+
+        print_success(&format!(
+            "Restored '{}' at {}",
+            removed.name,
+            shorten_path(&removed.path)
+        ));
+    }
+
+    store.save()?;
+
+    // Keep whatever couldn't be restored so it can be retried once the cause is fixed
+    if !failed.is_empty() {
+        print_warning(&format!(
+            "{} worktree(s) from `{}` were not restored and remain in the undo log.",
+            failed.len(),
+            operation.command
+        ));
+        log.push(Operation {
+            removed: failed,
+            ..operation
+        });
+    }
+    log.save()?;
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use crate::git::GitRepo;
+use crate::ui::print_success;
+use crate::worktree::{list_worktrees, resolve_worktree_exact};
+use anyhow::{bail, Result};
+
+pub fn execute(repo: &GitRepo, name: &str) -> Result<()> {
+    let worktrees = list_worktrees(repo)?;
+
+    let wt = resolve_worktree_exact(&worktrees, name)?;
+
+    if !wt.locked {
+        bail!("Worktree '{}' is not locked", name);
+    }
+
+    repo.unlock_worktree(&wt.path)?;
+
+    print_success(&format!("Unlocked worktree '{}'", name));
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::daemon;
+use crate::git::GitRepo;
+use crate::ui;
+use anyhow::{bail, Result};
+
+pub struct WatchOptions {
+    /// Report whether a watcher is running instead of starting one
+    pub status: bool,
+    pub stop: bool,
+}
+
+pub fn execute(repo: &GitRepo, opts: WatchOptions) -> Result<()> {
+    if opts.status {
+        let Some((pid, worktrees)) = daemon::ping(repo) else {
+            bail!("No watcher is running for this repository");
+        };
+        println!("Watching {} worktree(s) (pid {})", worktrees, pid);
+        return Ok(());
+    }
+
+    if opts.stop {
+        if !daemon::stop(repo) {
+            bail!("No watcher is running for this repository");
+        }
+        ui::print_success("Stopped the watcher");
+        return Ok(());
+    }
+
+    daemon::serve(repo)
+}
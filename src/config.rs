@@ -1,20 +1,79 @@
 use crate::git::GitRepo;
-use anyhow::{Context, Result};
+use crate::hooks;
+use crate::worktree::slug_from_branch;
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 const CONFIG_FILENAME: &str = "workty.toml";
 const DEFAULT_BASE: &str = "main";
+const DEFAULT_REMOTE: &str = "origin";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
     pub version: u32,
     pub base: String,
+    /// Remote whose copy of `base` statuses are compared against
+    pub remote: String,
     pub root: String,
-    pub layout: String,
+    pub layout: Layout,
     pub open_cmd: Option<String>,
+    /// Template for `new`, e.g. `{user}/{type}/{ticket}-{slug}`
+    pub branch_template: Option<String>,
+    /// Regex every new branch name must match
+    pub branch_pattern: Option<String>,
+    /// Globs copied from the main worktree into new worktrees
+    pub copy: Vec<String>,
+    /// Globs symlinked from the main worktree into new worktrees
+    pub link: Vec<String>,
+    /// Archive worktrees before `rm` and `clean` remove them
+    pub archive: bool,
+    pub hooks: HooksConfig,
+}
+
+/// How worktree directories are named and where they are placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// `<root>/feat-login`
+    #[default]
+    Flat,
+    /// `<root>/feat/login`
+    Nested,
+    /// `../<repo>.feat-login`, next to the main clone
+    Sibling,
+}
+
+impl std::fmt::Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layout::Flat => write!(f, "flat"),
+            Layout::Nested => write!(f, "nested"),
+            Layout::Sibling => write!(f, "sibling"),
+        }
+    }
+}
+
+/// Shell commands run inside a worktree at points in its lifecycle.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub post_create: Vec<String>,
+    pub pre_remove: Vec<String>,
+    pub post_switch: Vec<String>,
+    pub on_failure: HookFailure,
+}
+
+/// What to do when a hook command exits with a non-zero status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookFailure {
+    #[default]
+    Abort,
+    Warn,
 }
 
 impl Default for Config {
@@ -22,87 +81,384 @@ impl Default for Config {
         Self {
             version: 1,
             base: DEFAULT_BASE.to_string(),
+            remote: DEFAULT_REMOTE.to_string(),
             root: "~/.workty/{repo}-{id}".to_string(),
-            layout: "flat".to_string(),
+            layout: Layout::Flat,
             open_cmd: None,
+            branch_template: None,
+            branch_pattern: None,
+            copy: Vec::new(),
+            link: Vec::new(),
+            archive: false,
+            hooks: HooksConfig::default(),
         }
     }
 }
 
+/// Where an effective config value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    Default,
+    Detected,
+    File(PathBuf),
+    Env(String),
+}
+
+impl std::fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::Detected => write!(f, "detected"),
+            ConfigOrigin::File(path) => write!(f, "file:{}", path.display()),
+            ConfigOrigin::Env(var) => write!(f, "env:{}", var),
+        }
+    }
+}
+
+/// Effective config values keyed by dotted path (e.g. `hooks.on_failure`).
+pub type ConfigOrigins = BTreeMap<String, ConfigOrigin>;
+
+/// Valid keys that are absent from the defaults because they are optional.
+const OPTIONAL_KEYS: &[&str] = &["open_cmd", "branch_template", "branch_pattern"];
+
+/// Top-level keys that can be overridden with `WORKTY_<KEY>` environment variables.
+const ENV_KEYS: &[&str] = &[
+    "base",
+    "remote",
+    "root",
+    "layout",
+    "open_cmd",
+    "branch_template",
+    "branch_pattern",
+    "copy",
+    "link",
+    "archive",
+];
+
 impl Config {
     pub fn load(repo: &GitRepo) -> Result<Self> {
-        let mut candidates = vec![
-            // 1. Repo root
-            repo.root.join(CONFIG_FILENAME),
-            // 2. Git dir
-            config_path(repo),
-        ];
+        Self::load_with_origins(repo).map(|(config, _)| config)
+    }
 
-        // 3. User config dir (~/.config/workty/workty.toml)
-        if let Some(config_dir) = dirs::config_dir() {
-            candidates.push(config_dir.join("workty").join(CONFIG_FILENAME));
+    /// Merges defaults, user config, repo `workty.toml`, `.git/workty.toml` and
+    /// `WORKTY_*` environment variables, later layers winning per key.
+    pub fn load_with_origins(repo: &GitRepo) -> Result<(Self, ConfigOrigins)> {
+        let mut merged = default_table();
+        let mut origins = ConfigOrigins::new();
+        record_origins(&merged, "", &ConfigOrigin::Default, &mut origins);
+
+        for path in config_layers(repo) {
+            if !path.exists() {
+                continue;
+            }
+            let table = read_layer(&path)?;
+            merge_table(
+                &mut merged,
+                table,
+                "",
+                &ConfigOrigin::File(path),
+                &mut origins,
+            );
         }
 
-        if let Some(home) = dirs::home_dir() {
-            // 4. ~/.workty.toml
-            candidates.push(home.join(format!(".{}", CONFIG_FILENAME)));
-            // 5. ~/workty.toml
-            candidates.push(home.join(CONFIG_FILENAME));
-        }
-
-        let mut config: Self = candidates
-            .into_iter()
-            .find(|path| path.exists())
-            .map(|path| {
-                let contents = std::fs::read_to_string(&path)
-                    .with_context(|| format!("Failed to read config from {}", path.display()))?;
-                toml::from_str(&contents)
-                    .with_context(|| format!("Failed to parse config from {}", path.display()))
-            })
-            .transpose()?
-            .unwrap_or_default();
-
-        config.adjust_defaults(repo);
-
-        Ok(config)
-    }
-
-    fn adjust_defaults(&mut self, repo: &GitRepo) {
-        // If the base branch is the default one but it doesn't exist,
-        // we try to detect the actual default branch (e.g. master, trunk, etc)
-        if self.base == DEFAULT_BASE && !repo.branch_exists(DEFAULT_BASE) {
-            if let Some(default) = repo.default_branch() {
-                self.base = default;
+        let in_hook = std::env::var_os(hooks::HOOK_VAR).is_some();
+        for key in ENV_KEYS {
+            let var = env_var_name(key);
+            // Inside a hook, WORKTY_BASE describes the worktree rather than overriding
+            if in_hook && hooks::CONTEXT_VARS.contains(&var.as_str()) {
+                continue;
+            }
+            if let Ok(raw) = std::env::var(&var) {
+                merged.insert(key.to_string(), parse_value(&raw));
+                origins.insert(key.to_string(), ConfigOrigin::Env(var));
             }
         }
+
+        let mut config: Self = toml::Value::Table(merged)
+            .try_into()
+            .context("Invalid configuration")?;
+
+        if config.adjust_defaults(repo, &origins) {
+            origins.insert("base".to_string(), ConfigOrigin::Detected);
+        }
+
+        Ok((config, origins))
     }
 
-    #[allow(dead_code)]
-    pub fn save(&self, repo: &GitRepo) -> Result<()> {
-        let path = config_path(repo);
-        let contents = toml::to_string_pretty(self).context("Failed to serialize config")?;
-        std::fs::write(&path, contents)
-            .with_context(|| format!("Failed to write config to {}", path.display()))
+    /// Returns true if a default was replaced by a detected value.
+    fn adjust_defaults(&mut self, repo: &GitRepo, origins: &ConfigOrigins) -> bool {
+        // If the base branch was never configured, follow the remote's default
+        // branch (e.g. develop, trunk), falling back to a local main or master
+        let base_is_default = origins.get("base") == Some(&ConfigOrigin::Default);
+        if base_is_default {
+            if let Some(default) = repo.default_branch(&self.remote) {
+                if default != self.base {
+                    self.base = default;
+                    return true;
+                }
+            }
+        }
+        false
     }
 
     pub fn workspace_root(&self, repo: &GitRepo) -> PathBuf {
-        let repo_name = repo
-            .root
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("repo");
+        let repo_name = repo_name(repo);
 
         let id = compute_repo_id(repo);
 
-        let expanded = self.root.replace("{repo}", repo_name).replace("{id}", &id);
+        let expanded = self.root.replace("{repo}", &repo_name).replace("{id}", &id);
 
         expand_tilde(&expanded)
     }
 
-    pub fn worktree_path(&self, repo: &GitRepo, branch_slug: &str) -> PathBuf {
-        let root = self.workspace_root(repo);
-        root.join(branch_slug)
+    /// Where the worktree for `branch` lives under the configured layout.
+    pub fn worktree_path(&self, repo: &GitRepo, branch: &str) -> PathBuf {
+        match self.layout {
+            Layout::Flat => self.workspace_root(repo).join(slug_from_branch(branch)),
+            Layout::Nested => branch
+                .split('/')
+                .map(slug_from_branch)
+                .filter(|part| !part.is_empty())
+                .fold(self.workspace_root(repo), |path, part| path.join(part)),
+            Layout::Sibling => {
+                let main = repo.main_worktree_path();
+                let parent = main.parent().unwrap_or(&main);
+                parent.join(format!("{}.{}", repo_name(repo), slug_from_branch(branch)))
+            }
+        }
+    }
+}
+
+/// Config files in increasing order of precedence.
+pub fn config_layers(repo: &GitRepo) -> Vec<PathBuf> {
+    let mut layers = Vec::new();
+
+    if let Some(home) = dirs::home_dir() {
+        layers.push(home.join(CONFIG_FILENAME));
+        layers.push(home.join(format!(".{}", CONFIG_FILENAME)));
+    }
+
+    if let Some(config_dir) = dirs::config_dir() {
+        layers.push(config_dir.join("workty").join(CONFIG_FILENAME));
+    }
+
+    layers.push(repo.root.join(CONFIG_FILENAME));
+    layers.push(config_path(repo));
+
+    layers
+}
+
+fn read_layer(path: &Path) -> Result<toml::Table> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config from {}", path.display()))?;
+
+    // Parse as Config first so type errors point at the offending file
+    toml::from_str::<Config>(&contents)
+        .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+
+    toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse config from {}", path.display()))
+}
+
+fn default_table() -> toml::Table {
+    match toml::Value::try_from(Config::default()) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => toml::Table::new(),
+    }
+}
+
+/// Every `WORKTY_<KEY>` variable that overrides a config value.
+pub fn env_override_vars() -> impl Iterator<Item = String> {
+    ENV_KEYS.iter().map(|key| env_var_name(key))
+}
+
+fn env_var_name(key: &str) -> String {
+    format!("WORKTY_{}", key.to_uppercase())
+}
+
+/// Parses a TOML value (`true`, `3`, `["a"]`), falling back to a plain string.
+pub fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn record_origins(
+    table: &toml::Table,
+    prefix: &str,
+    origin: &ConfigOrigin,
+    origins: &mut ConfigOrigins,
+) {
+    for (key, value) in table {
+        let full_key = join_key(prefix, key);
+        match value {
+            toml::Value::Table(inner) => record_origins(inner, &full_key, origin, origins),
+            _ => {
+                origins.insert(full_key, origin.clone());
+            }
+        }
+    }
+}
+
+fn merge_table(
+    base: &mut toml::Table,
+    overlay: toml::Table,
+    prefix: &str,
+    origin: &ConfigOrigin,
+    origins: &mut ConfigOrigins,
+) {
+    for (key, value) in overlay {
+        let full_key = join_key(prefix, &key);
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(inner)) => {
+                merge_table(existing, inner, &full_key, origin, origins);
+            }
+            (_, value) => {
+                if let toml::Value::Table(inner) = &value {
+                    record_origins(inner, &full_key, origin, origins);
+                } else {
+                    origins.insert(full_key, origin.clone());
+                }
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Flattens a config into `(dotted key, value)` pairs, sorted by key.
+pub fn flatten(config: &Config) -> Vec<(String, toml::Value)> {
+    fn walk(table: &toml::Table, prefix: &str, out: &mut Vec<(String, toml::Value)>) {
+        for (key, value) in table {
+            let full_key = join_key(prefix, key);
+            match value {
+                toml::Value::Table(inner) => walk(inner, &full_key, out),
+                _ => out.push((full_key, value.clone())),
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    if let Ok(toml::Value::Table(table)) = toml::Value::try_from(config) {
+        walk(&table, "", &mut out);
+    }
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+/// Every dotted key accepted by `git workty config set`.
+pub fn known_keys() -> Vec<String> {
+    let mut keys: Vec<String> = flatten(&Config::default())
+        .into_iter()
+        .map(|(key, _)| key)
+        .chain(OPTIONAL_KEYS.iter().map(|key| key.to_string()))
+        .collect();
+    keys.sort();
+    keys
+}
+
+fn check_key(key: &str) -> Result<()> {
+    if !known_keys().iter().any(|known| known == key) {
+        bail!(
+            "Unknown config key '{}'. Valid keys: {}",
+            key,
+            known_keys().join(", ")
+        );
     }
+    Ok(())
+}
+
+/// Sets `key` in the given file, keeping existing comments and formatting.
+pub fn set_value(path: &Path, key: &str, raw: &str) -> Result<()> {
+    check_key(key)?;
+    let mut value: toml_edit::Value = raw.parse().unwrap_or_else(|_| toml_edit::Value::from(raw));
+
+    edit_document(path, |doc| {
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let last = parts.pop().unwrap_or(key);
+        let mut item = doc.as_item_mut();
+        for part in parts {
+            item = &mut item[part];
+        }
+        // Keep inline comments attached to the old value
+        if let Some(existing) = item.get(last).and_then(|i| i.as_value()) {
+            *value.decor_mut() = existing.decor().clone();
+        }
+        item[last] = toml_edit::Item::Value(value);
+        Ok(())
+    })
+}
+
+/// Removes `key` from the given file. Returns false if it wasn't set there.
+pub fn unset_value(path: &Path, key: &str) -> Result<bool> {
+    check_key(key)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    edit_document(path, |doc| {
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let last = parts.pop().unwrap_or(key);
+        let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+        for part in parts {
+            match table
+                .get_mut(part)
+                .and_then(|item| item.as_table_like_mut())
+            {
+                Some(inner) => table = inner,
+                None => return Ok(false),
+            }
+        }
+        Ok(table.remove(last).is_some())
+    })
+}
+
+fn edit_document<T>(
+    path: &Path,
+    edit: impl FnOnce(&mut toml_edit::DocumentMut) -> Result<T>,
+) -> Result<T> {
+    let contents = if path.exists() {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {}", path.display()))?
+    } else {
+        String::new()
+    };
+
+    let mut doc: toml_edit::DocumentMut = contents
+        .parse()
+        .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+
+    let result = edit(&mut doc)?;
+
+    let updated = doc.to_string();
+    toml::from_str::<Config>(&updated).context("Invalid value")?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    std::fs::write(path, updated)
+        .with_context(|| format!("Failed to write config to {}", path.display()))?;
+
+    Ok(result)
+}
+
+/// Checks that a config file parses and matches the schema.
+pub fn validate_file(path: &Path) -> Result<()> {
+    read_layer(path).map(|_| ())
+}
+
+/// The user-level config file written by `git workty config --user`.
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("workty").join(CONFIG_FILENAME))
 }
 
 pub fn config_path(repo: &GitRepo) -> PathBuf {
@@ -113,7 +469,16 @@ pub fn config_exists(repo: &GitRepo) -> bool {
     config_path(repo).exists()
 }
 
-fn compute_repo_id(repo: &GitRepo) -> String {
+fn repo_name(repo: &GitRepo) -> String {
+    repo.main_worktree_path()
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("repo")
+        .to_string()
+}
+
+/// Short stable hash of the origin URL (or common dir), used for `{id}` and archives.
+pub fn compute_repo_id(repo: &GitRepo) -> String {
     let input = repo
         .origin_url()
         .unwrap_or_else(|| repo.common_dir.to_string_lossy().to_string());
@@ -154,7 +519,7 @@ mod tests {
         let config = Config::default();
         assert_eq!(config.version, 1);
         assert_eq!(config.base, "main");
-        assert_eq!(config.layout, "flat");
+        assert_eq!(config.layout, Layout::Flat);
     }
 
     #[test]
@@ -187,15 +552,142 @@ mod tests {
         let config = Config {
             version: 1,
             base: "develop".to_string(),
+            remote: "upstream".to_string(),
             root: "~/.worktrees/{repo}".to_string(),
-            layout: "flat".to_string(),
+            layout: Layout::Flat,
             open_cmd: Some("code".to_string()),
+            branch_template: Some("{user}/{type}/{ticket}-{slug}".to_string()),
+            branch_pattern: None,
+            copy: vec![".env".to_string()],
+            link: vec!["node_modules".to_string()],
+            archive: true,
+            hooks: HooksConfig {
+                post_create: vec!["npm ci".to_string()],
+                on_failure: HookFailure::Warn,
+                ..Default::default()
+            },
         };
 
         let serialized = toml::to_string_pretty(&config).unwrap();
         let deserialized: Config = toml::from_str(&serialized).unwrap();
 
         assert_eq!(config.base, deserialized.base);
+        assert_eq!(config.remote, deserialized.remote);
         assert_eq!(config.open_cmd, deserialized.open_cmd);
+        assert_eq!(config.copy, deserialized.copy);
+        assert_eq!(config.link, deserialized.link);
+        assert_eq!(config.hooks.post_create, deserialized.hooks.post_create);
+        assert_eq!(deserialized.hooks.on_failure, HookFailure::Warn);
+    }
+
+    #[test]
+    fn test_merge_table_per_key_precedence() {
+        let mut merged = default_table();
+        let mut origins = ConfigOrigins::new();
+        record_origins(&merged, "", &ConfigOrigin::Default, &mut origins);
+
+        let user: toml::Table = toml::from_str(
+            r#"
+            open_cmd = "code"
+            base = "develop"
+            [hooks]
+            post_create = ["npm ci"]
+            "#,
+        )
+        .unwrap();
+        let user_origin = ConfigOrigin::File(PathBuf::from("/home/me/.workty.toml"));
+        merge_table(&mut merged, user, "", &user_origin, &mut origins);
+
+        let repo: toml::Table = toml::from_str(
+            r#"
+            base = "main"
+            [hooks]
+            on_failure = "warn"
+            "#,
+        )
+        .unwrap();
+        let repo_origin = ConfigOrigin::File(PathBuf::from("/repo/.git/workty.toml"));
+        merge_table(&mut merged, repo, "", &repo_origin, &mut origins);
+
+        let config: Config = toml::Value::Table(merged).try_into().unwrap();
+        assert_eq!(config.base, "main");
+        assert_eq!(config.open_cmd.as_deref(), Some("code"));
+        assert_eq!(config.hooks.post_create, vec!["npm ci".to_string()]);
+        assert_eq!(config.hooks.on_failure, HookFailure::Warn);
+
+        assert_eq!(origins["base"], repo_origin);
+        assert_eq!(origins["open_cmd"], user_origin);
+        assert_eq!(origins["hooks.post_create"], user_origin);
+        assert_eq!(origins["hooks.on_failure"], repo_origin);
+        assert_eq!(origins["root"], ConfigOrigin::Default);
+    }
+
+    #[test]
+    fn test_set_and_unset_preserve_comments() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("workty.toml");
+        std::fs::write(&path, "# team defaults\nbase = \"main\" # trunk\n").unwrap();
+
+        set_value(&path, "base", "develop").unwrap();
+        set_value(&path, "hooks.on_failure", "warn").unwrap();
+        set_value(&path, "copy", r#"[".env"]"#).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("# team defaults"));
+        assert!(contents.contains("base = \"develop\" # trunk"));
+
+        let config: Config = toml::from_str(&contents).unwrap();
+        assert_eq!(config.hooks.on_failure, HookFailure::Warn);
+        assert_eq!(config.copy, vec![".env".to_string()]);
+
+        assert!(unset_value(&path, "hooks.on_failure").unwrap());
+        assert!(!unset_value(&path, "open_cmd").unwrap());
+        let config: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config.hooks.on_failure, HookFailure::Abort);
+    }
+
+    #[test]
+    fn test_set_value_rejects_invalid() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("workty.toml");
+
+        assert!(set_value(&path, "no_such_key", "1").is_err());
+        assert!(set_value(&path, "hooks.on_failure", "explode").is_err());
+        assert!(set_value(&path, "version", "not-a-number").is_err());
+        assert!(!path.exists(), "Invalid values must not be written");
+    }
+
+    #[test]
+    fn test_layout_parse() {
+        let config: Config = toml::from_str("layout = \"sibling\"").unwrap();
+        assert_eq!(config.layout, Layout::Sibling);
+        assert!(toml::from_str::<Config>("layout = \"tree\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("main"), toml::Value::String("main".to_string()));
+        assert_eq!(parse_value("true"), toml::Value::Boolean(true));
+        assert_eq!(
+            parse_value(r#"[".env"]"#),
+            toml::Value::Array(vec![toml::Value::String(".env".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_hooks_config_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            base = "main"
+
+            [hooks]
+            post_create = ["cp ../.env .env", "npm ci"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.hooks.post_create.len(), 2);
+        assert!(config.hooks.pre_remove.is_empty());
+        assert_eq!(config.hooks.on_failure, HookFailure::Abort);
     }
 }
@@ -0,0 +1,159 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default)]
+pub struct CopySummary {
+    pub copied: usize,
+    pub linked: usize,
+}
+
+/// Copies or symlinks the files matched by `copy` and `link` from `source`
+/// (the main worktree) into a freshly created worktree at `dest`.
+///
+/// Paths that already exist in `dest` (e.g. tracked files) are left alone.
+/// Copies use copy-on-write reflinks where the filesystem supports them.
+pub fn seed_worktree(config: &Config, source: &Path, dest: &Path) -> Result<CopySummary> {
+    let mut summary = CopySummary::default();
+
+    for matched in expand_patterns(source, &config.copy)? {
+        let target = dest.join(matched.strip_prefix(source).unwrap_or(&matched));
+        if target.symlink_metadata().is_ok() {
+            continue;
+        }
+        copy_recursive(&matched, &target)?;
+        summary.copied += 1;
+    }
+
+    for matched in expand_patterns(source, &config.link)? {
+        let target = dest.join(matched.strip_prefix(source).unwrap_or(&matched));
+        if target.symlink_metadata().is_ok() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        symlink(&matched, &target).with_context(|| {
+            format!(
+                "Failed to link {} to {}",
+                target.display(),
+                matched.display()
+            )
+        })?;
+        summary.linked += 1;
+    }
+
+    Ok(summary)
+}
+
+fn expand_patterns(source: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let root = glob::Pattern::escape(&source.to_string_lossy());
+    let mut matches = Vec::new();
+
+    for pattern in patterns {
+        let full = format!("{}/{}", root, pattern.trim_start_matches('/'));
+        let paths =
+            glob::glob(&full).with_context(|| format!("Invalid glob pattern '{}'", pattern))?;
+        for path in paths.flatten() {
+            if path.starts_with(source.join(".git")) {
+                continue;
+            }
+            if !matches.contains(&path) {
+                matches.push(path);
+            }
+        }
+    }
+
+    Ok(matches)
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(from)
+        .with_context(|| format!("Failed to read {}", from.display()))?;
+
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    if metadata.file_type().is_symlink() {
+        let target = std::fs::read_link(from)
+            .with_context(|| format!("Failed to read link {}", from.display()))?;
+        return symlink(&target, to)
+            .with_context(|| format!("Failed to create link {}", to.display()));
+    }
+
+    if metadata.is_dir() {
+        std::fs::create_dir_all(to)
+            .with_context(|| format!("Failed to create directory: {}", to.display()))?;
+        for entry in std::fs::read_dir(from)
+            .with_context(|| format!("Failed to read directory: {}", from.display()))?
+        {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+
+    reflink_copy::reflink_or_copy(from, to)
+        .with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    if original.is_dir() {
+        std::os::windows::fs::symlink_dir(original, link)
+    } else {
+        std::os::windows::fs::symlink_file(original, link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_worktree_copies_and_links() {
+        let source = tempfile::TempDir::new().unwrap();
+        let dest = tempfile::TempDir::new().unwrap();
+
+        std::fs::write(source.path().join(".env"), "SECRET=1").unwrap();
+        std::fs::write(source.path().join(".env.local"), "LOCAL=1").unwrap();
+        std::fs::create_dir_all(source.path().join("node_modules/pkg")).unwrap();
+        std::fs::write(source.path().join("node_modules/pkg/index.js"), "").unwrap();
+        std::fs::write(dest.path().join(".env.local"), "KEEP=1").unwrap();
+
+        let config = Config {
+            copy: vec![".env*".to_string()],
+            link: vec!["node_modules".to_string()],
+            ..Default::default()
+        };
+
+        let summary = seed_worktree(&config, source.path(), dest.path()).unwrap();
+
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.linked, 1);
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join(".env")).unwrap(),
+            "SECRET=1"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join(".env.local")).unwrap(),
+            "KEEP=1"
+        );
+        assert!(dest
+            .path()
+            .join("node_modules")
+            .symlink_metadata()
+            .unwrap()
+            .file_type()
+            .is_symlink());
+    }
+}
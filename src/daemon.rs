@@ -0,0 +1,510 @@
+use crate::git::GitRepo;
+use crate::metadata::now_timestamp;
+use crate::status::{refresh_changes, WorktreeStatus};
+use crate::worktree::Worktree;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use crate::config::Config;
+#[cfg(unix)]
+use crate::metadata::METADATA_FILENAME;
+#[cfg(unix)]
+use crate::status::{get_worktree_status, BaseRefs};
+#[cfg(unix)]
+use crate::status_cache::Fingerprint;
+#[cfg(unix)]
+use crate::ui;
+#[cfg(unix)]
+use crate::worktree::list_worktrees;
+#[cfg(unix)]
+use anyhow::{bail, Context};
+#[cfg(unix)]
+use notify::{EventKind, RecursiveMode, Watcher};
+#[cfg(unix)]
+use std::collections::{HashMap, HashSet};
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+#[cfg(unix)]
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(unix)]
+use std::time::{Duration, Instant};
+
+const SOCKET_FILENAME: &str = "workty-watch.sock";
+
+/// How long `list` and `pick` wait on the daemon before computing statuses themselves.
+#[cfg(unix)]
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Events arriving this close together are handled as one batch, so a rebase
+/// rewriting HEAD, the index and a ref doesn't recompute three times.
+#[cfg(unix)]
+const DEBOUNCE: Duration = Duration::from_millis(100);
+/// Edits to tracked files don't touch the index, so everything is recomputed this
+/// often regardless of events.
+#[cfg(unix)]
+const FULL_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Clients re-check the uncommitted changes of statuses older than this, since
+/// the daemon only sees file edits on its next full refresh.
+const DIRTY_TTL_SECS: i64 = 2;
+
+/// One JSON object per line, e.g. `{"request":"statuses"}`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    Ping,
+    Statuses,
+    Shutdown,
+}
+
+/// Sent back as a single JSON line, tagged with `response`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    Pong { pid: u32, worktrees: usize },
+    Statuses { worktrees: Vec<WatchedStatus> },
+    ShuttingDown,
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedStatus {
+    pub path: PathBuf,
+    pub status: WorktreeStatus,
+    /// Unix timestamp of when the daemon computed `status`
+    #[serde(default)]
+    pub computed_at: i64,
+}
+
+pub fn socket_path(repo: &GitRepo) -> PathBuf {
+    repo.common_dir.join(SOCKET_FILENAME)
+}
+
+/// Statuses from a running `git workty watch`, or `None` if there isn't one or it
+/// hasn't seen every worktree in `worktrees` yet. Uncommitted changes are checked
+/// again here for statuses older than `DIRTY_TTL_SECS`.
+pub fn query_statuses(
+    repo: &GitRepo,
+    worktrees: &[Worktree],
+) -> Option<Vec<(Worktree, WorktreeStatus)>> {
+    let Response::Statuses { worktrees: watched } = send(repo, &Request::Statuses)? else {
+        return None;
+    };
+
+    let mut by_path: std::collections::HashMap<PathBuf, WatchedStatus> =
+        watched.into_iter().map(|w| (w.path.clone(), w)).collect();
+    let found: Vec<(Worktree, WatchedStatus)> = worktrees
+        .iter()
+        .map(|wt| Some((wt.clone(), by_path.remove(&wt.path)?)))
+        .collect::<Option<_>>()?;
+
+    let now = now_timestamp();
+    Some(
+        found
+            .into_par_iter()
+            .map(|(wt, watched)| {
+                let mut status = watched.status;
+                if now - watched.computed_at >= DIRTY_TTL_SECS {
+                    refresh_changes(&wt, &mut status);
+                }
+                (wt, status)
+            })
+            .collect(),
+    )
+}
+
+/// The daemon's pid and how many worktrees it watches, if one is running.
+pub fn ping(repo: &GitRepo) -> Option<(u32, usize)> {
+    match send(repo, &Request::Ping)? {
+        Response::Pong { pid, worktrees } => Some((pid, worktrees)),
+        _ => None,
+    }
+}
+
+/// Asks a running daemon to exit. Returns false if none answered.
+pub fn stop(repo: &GitRepo) -> bool {
+    matches!(send(repo, &Request::Shutdown), Some(Response::ShuttingDown))
+}
+
+#[cfg(unix)]
+fn send(repo: &GitRepo, request: &Request) -> Option<Response> {
+    let mut stream = UnixStream::connect(socket_path(repo)).ok()?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+
+    let mut line = serde_json::to_string(request).ok()?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).ok()?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).ok()?;
+    serde_json::from_str(&response).ok()
+}
+
+#[cfg(not(unix))]
+fn send(_repo: &GitRepo, _request: &Request) -> Option<Response> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn serve(_repo: &GitRepo) -> anyhow::Result<()> {
+    anyhow::bail!("`git workty watch` needs Unix domain sockets, which this platform lacks")
+}
+
+/// Watches every worktree's HEAD and index, and the shared refs, and answers
+/// requests on the socket until asked to shut down.
+#[cfg(unix)]
+pub fn serve(repo: &GitRepo) -> anyhow::Result<()> {
+    let path = socket_path(repo);
+    if UnixStream::connect(&path).is_ok() {
+        bail!("A watcher is already running for this repository");
+    }
+    // Left behind by a watcher that was killed
+    let _ = std::fs::remove_file(&path);
+
+    let (tx, rx) = mpsc::channel();
+    let fs_tx = tx.clone();
+    let watcher = notify::recommended_watcher(move |event| {
+        let _ = fs_tx.send(Message::Fs(event));
+    })
+    .context("Failed to start filesystem watcher")?;
+
+    let mut state = WatchState::new(repo, watcher)?;
+    state.refresh_all()?;
+
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    let _socket = SocketGuard(path);
+    let entries = Arc::clone(&state.entries);
+    std::thread::spawn(move || serve_clients(listener, entries, tx));
+
+    ui::print_info(&format!(
+        "Watching {} worktree(s); press Ctrl-C to stop.",
+        state.worktrees.len()
+    ));
+
+    let mut last_full = Instant::now();
+    loop {
+        let timeout = FULL_REFRESH_INTERVAL.saturating_sub(last_full.elapsed());
+        let mut next = match rx.recv_timeout(timeout) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => {
+                if let Err(e) = state.refresh_all() {
+                    ui::print_warning(&format!("{:#}", e));
+                }
+                last_full = Instant::now();
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+
+        let mut changes = Changes::default();
+        while let Some(message) = next {
+            match message {
+                Message::Shutdown => return Ok(()),
+                Message::Fs(Ok(event)) => {
+                    // Reading HEAD to compute a status must not trigger another one
+                    if !matches!(event.kind, EventKind::Access(_)) {
+                        for path in &event.paths {
+                            state.classify(path, &mut changes);
+                        }
+                    }
+                }
+                // Events were lost (e.g. the queue overflowed), so check everything
+                Message::Fs(Err(_)) => changes.relist = true,
+            }
+            next = rx.recv_timeout(DEBOUNCE).ok();
+        }
+
+        if let Err(e) = state.apply(changes) {
+            ui::print_warning(&format!("{:#}", e));
+        }
+    }
+}
+
+#[cfg(unix)]
+enum Message {
+    Fs(notify::Result<notify::Event>),
+    Shutdown,
+}
+
+/// Removes the socket when the daemon exits normally.
+#[cfg(unix)]
+struct SocketGuard(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(unix)]
+struct Entry {
+    fingerprint: Option<Fingerprint>,
+    status: WorktreeStatus,
+    computed_at: i64,
+}
+
+#[cfg(unix)]
+type Entries = Arc<Mutex<HashMap<PathBuf, Entry>>>;
+
+/// What a batch of filesystem events touched.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+struct Changes {
+    /// Worktrees were added, removed, locked or unlocked
+    relist: bool,
+    /// Refs or base overrides moved, which can affect any worktree
+    refs: bool,
+    /// Git dirs whose HEAD or index changed
+    git_dirs: HashSet<PathBuf>,
+}
+
+#[cfg(unix)]
+struct WatchState<'a> {
+    repo: &'a GitRepo,
+    watcher: notify::RecommendedWatcher,
+    common_dir: PathBuf,
+    worktrees: Vec<Worktree>,
+    bases: BaseRefs,
+    /// Each worktree's git dir, e.g. `.git/worktrees/feat`, mapped to its path
+    git_dirs: HashMap<PathBuf, PathBuf>,
+    entries: Entries,
+}
+
+#[cfg(unix)]
+impl<'a> WatchState<'a> {
+    fn new(repo: &'a GitRepo, mut watcher: notify::RecommendedWatcher) -> anyhow::Result<Self> {
+        // Event paths are built from the watched paths, so watch canonical ones
+        let common_dir = repo
+            .common_dir
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", repo.common_dir.display()))?;
+
+        watcher
+            .watch(&common_dir, RecursiveMode::NonRecursive)
+            .context("Failed to watch the git directory")?;
+        watcher
+            .watch(&common_dir.join("refs"), RecursiveMode::Recursive)
+            .context("Failed to watch refs")?;
+
+        Ok(Self {
+            repo,
+            watcher,
+            common_dir,
+            worktrees: Vec::new(),
+            bases: BaseRefs::default(),
+            git_dirs: HashMap::new(),
+            entries: Arc::default(),
+        })
+    }
+
+    /// Re-reads the worktree list and base refs, and drops entries for worktrees
+    /// that are gone.
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let config = Config::load(self.repo)?;
+        self.worktrees = list_worktrees(self.repo)?;
+        self.bases = BaseRefs::load(self.repo, &config, &self.worktrees);
+
+        self.git_dirs = self
+            .worktrees
+            .iter()
+            .filter_map(|wt| {
+                let git_dir = git2::Repository::open(&wt.path).ok()?.path().canonicalize();
+                Some((git_dir.ok()?, wt.path.clone()))
+            })
+            .collect();
+
+        // Linked worktrees' git dirs live here; it only exists once there is one
+        let linked = self.common_dir.join("worktrees");
+        if linked.is_dir() {
+            let _ = self.watcher.watch(&linked, RecursiveMode::Recursive);
+        }
+
+        let live: HashSet<&PathBuf> = self.worktrees.iter().map(|wt| &wt.path).collect();
+        lock(&self.entries).retain(|path, _| live.contains(path));
+        Ok(())
+    }
+
+    fn refresh_all(&mut self) -> anyhow::Result<()> {
+        self.reload()?;
+        self.update(&self.worktrees.iter().collect::<Vec<_>>(), true);
+        Ok(())
+    }
+
+    fn classify(&self, path: &Path, changes: &mut Changes) {
+        let Ok(relative) = path.strip_prefix(&self.common_dir) else {
+            return;
+        };
+        let components: Vec<&str> = relative
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect();
+        if components
+            .last()
+            .is_some_and(|name| name.ends_with(".lock"))
+        {
+            return;
+        }
+
+        match components.as_slice() {
+            ["refs", ..] | ["packed-refs"] | [METADATA_FILENAME] => changes.refs = true,
+            ["HEAD"] | ["index"] => {
+                changes.git_dirs.insert(self.common_dir.clone());
+            }
+            ["worktrees", name, "HEAD" | "index"] => {
+                changes
+                    .git_dirs
+                    .insert(self.common_dir.join("worktrees").join(name));
+            }
+            ["worktrees"] | ["worktrees", _] | ["worktrees", _, "gitdir" | "locked"] => {
+                changes.relist = true
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(&mut self, changes: Changes) -> anyhow::Result<()> {
+        if changes.relist || changes.refs {
+            self.reload()?;
+        }
+
+        let touched_paths: HashSet<&PathBuf> = changes
+            .git_dirs
+            .iter()
+            .filter_map(|git_dir| self.git_dirs.get(git_dir))
+            .collect();
+        let (touched, rest): (Vec<&Worktree>, Vec<&Worktree>) = self
+            .worktrees
+            .iter()
+            .partition(|wt| touched_paths.contains(&wt.path));
+
+        self.update(&touched, true);
+        if changes.relist || changes.refs {
+            // Only worktrees whose HEAD, upstream or base actually moved get recomputed
+            self.update(&rest, false);
+        }
+        Ok(())
+    }
+
+    /// Recomputes the given worktrees' statuses. Unless `force` is set, ones whose
+    /// fingerprint hasn't changed are skipped.
+    fn update(&self, worktrees: &[&Worktree], force: bool) {
+        let updated: Vec<(PathBuf, Entry)> = worktrees
+            .par_iter()
+            .filter_map(|wt| {
+                let base = self.bases.get(&wt.path);
+                let fingerprint = Fingerprint::compute(wt, base);
+                if !force && fingerprint.is_some() {
+                    let entries = lock(&self.entries);
+                    if entries.get(&wt.path).map(|e| &e.fingerprint) == Some(&fingerprint) {
+                        return None;
+                    }
+                }
+
+                let entry = Entry {
+                    fingerprint,
+                    status: get_worktree_status(wt, base),
+                    computed_at: now_timestamp(),
+                };
+                Some((wt.path.clone(), entry))
+            })
+            .collect();
+
+        lock(&self.entries).extend(updated);
+    }
+}
+
+#[cfg(unix)]
+fn lock(entries: &Entries) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Entry>> {
+    // A panicked update leaves the map usable, just possibly stale
+    entries.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Answers one request per connection, in order.
+#[cfg(unix)]
+fn serve_clients(listener: UnixListener, entries: Entries, tx: Sender<Message>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+
+        let mut line = String::new();
+        let mut reader = BufReader::new(&stream);
+        if reader.read_line(&mut line).is_err() {
+            continue;
+        }
+
+        let request = serde_json::from_str::<Request>(&line);
+        let response = match request {
+            Ok(Request::Ping) => Response::Pong {
+                pid: std::process::id(),
+                worktrees: lock(&entries).len(),
+            },
+            Ok(Request::Statuses) => {
+                let now = now_timestamp();
+                let worktrees = lock(&entries)
+                    .iter()
+                    .map(|(path, entry)| {
+                        let mut status = entry.status.clone();
+                        // Stored relative to when it was computed
+                        let age = (now - entry.computed_at).max(0);
+                        status.last_commit_time = status.last_commit_time.map(|t| t + age);
+                        WatchedStatus {
+                            path: path.clone(),
+                            status,
+                            computed_at: entry.computed_at,
+                        }
+                    })
+                    .collect();
+                Response::Statuses { worktrees }
+            }
+            Ok(Request::Shutdown) => Response::ShuttingDown,
+            Err(e) => Response::Error {
+                message: format!("Invalid request: {}", e),
+            },
+        };
+
+        if let Ok(mut json) = serde_json::to_string(&response) {
+            json.push('\n');
+            let _ = (&stream).write_all(json.as_bytes());
+        }
+
+        if matches!(response, Response::ShuttingDown) {
+            let _ = tx.send(Message::Shutdown);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_is_tagged_json_lines() {
+        assert_eq!(
+            serde_json::to_string(&Request::Statuses).unwrap(),
+            r#"{"request":"statuses"}"#
+        );
+
+        let response: Response =
+            serde_json::from_str(r#"{"response":"pong","pid":42,"worktrees":3}"#).unwrap();
+        assert!(matches!(
+            response,
+            Response::Pong {
+                pid: 42,
+                worktrees: 3
+            }
+        ));
+    }
+}
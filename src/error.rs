@@ -0,0 +1,204 @@
+use crate::git::GitError;
+use crate::status::Operation;
+use crate::ui::JSON_SCHEMA_VERSION;
+use serde::Serialize;
+
+/// Exit code for errors without a more specific one.
+pub const EXIT_FAILURE: i32 = 1;
+/// A named worktree does not exist, or the name matches several.
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// Refused because a worktree is dirty, locked or in the middle of a rebase, merge, etc.
+pub const EXIT_REFUSED: i32 = 4;
+/// A git command or libgit2 call failed.
+pub const EXIT_GIT_FAILED: i32 = 5;
+/// The GitHub CLI is missing or not authenticated.
+pub const EXIT_GH_MISSING: i32 = 6;
+/// The user answered no to a confirmation prompt.
+pub const EXIT_DECLINED: i32 = 7;
+/// The user cancelled a picker (128 + SIGINT, like an interrupted shell).
+pub const EXIT_ABORTED: i32 = 130;
+
+/// Failures scripts need to tell apart, each with its own exit code.
+#[derive(Debug, thiserror::Error)]
+pub enum WorktyError {
+    #[error("Worktree '{0}' not found")]
+    WorktreeNotFound(String),
+    #[error("'{name}' matches several worktrees: {}", candidates.join(", "))]
+    Ambiguous {
+        name: String,
+        candidates: Vec<String>,
+    },
+    #[error("Worktree '{name}' has uncommitted changes")]
+    Dirty { name: String },
+    #[error("Worktree '{name}' is locked{}", reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default())]
+    Locked {
+        name: String,
+        reason: Option<String>,
+    },
+    #[error("Worktree '{name}' is {}", operation.label())]
+    InProgress { name: String, operation: Operation },
+    #[error("Declined at the confirmation prompt; nothing was changed")]
+    Declined,
+    #[error("Aborted")]
+    Aborted,
+    #[error("git {command} failed: {stderr}")]
+    GitFailed { command: String, stderr: String },
+    #[error("GitHub CLI (gh) is not installed")]
+    GhMissing,
+    #[error("GitHub CLI is not authenticated")]
+    GhNotAuthenticated,
+}
+
+impl WorktyError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::WorktreeNotFound(_) | Self::Ambiguous { .. } => EXIT_NOT_FOUND,
+            Self::Dirty { .. } | Self::Locked { .. } | Self::InProgress { .. } => EXIT_REFUSED,
+            Self::Declined => EXIT_DECLINED,
+            Self::Aborted => EXIT_ABORTED,
+            Self::GitFailed { .. } => EXIT_GIT_FAILED,
+            Self::GhMissing | Self::GhNotAuthenticated => EXIT_GH_MISSING,
+        }
+    }
+
+    /// Stable identifier for `--json` output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::WorktreeNotFound(_) => "worktree_not_found",
+            Self::Ambiguous { .. } => "ambiguous",
+            Self::Dirty { .. } => "dirty",
+            Self::Locked { .. } => "locked",
+            Self::InProgress { .. } => "in_progress",
+            Self::Declined => "declined",
+            Self::Aborted => "aborted",
+            Self::GitFailed { .. } => "git_failed",
+            Self::GhMissing => "gh_missing",
+            Self::GhNotAuthenticated => "gh_not_authenticated",
+        }
+    }
+
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::WorktreeNotFound(_) => {
+                Some("Use `git workty list` to see available worktrees.".to_string())
+            }
+            Self::Ambiguous { .. } => {
+                Some("Use the full branch name, or a longer part of it.".to_string())
+            }
+            Self::Dirty { .. } => {
+                Some("Commit or stash the changes, or pass --force to discard them.".to_string())
+            }
+            Self::Locked { name, .. } => Some(format!(
+                "Run `git workty unlock {}` first, or pass --force twice to `rm` or `clean`.",
+                name
+            )),
+            Self::InProgress { operation, .. } => Some(format!(
+                "Finish it, or run `{}` in that worktree. Pass --force to go ahead anyway.",
+                operation.abort_command()
+            )),
+            Self::Declined | Self::Aborted | Self::GitFailed { .. } => None,
+            Self::GhMissing => Some("Install it from https://cli.github.com/".to_string()),
+            Self::GhNotAuthenticated => Some("Run `gh auth login` to authenticate.".to_string()),
+        }
+    }
+}
+
+/// What gets reported for a failed command: the full context chain, plus the kind,
+/// hint and exit code of the first typed error found in it.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub kind: &'static str,
+    pub message: String,
+    pub hint: Option<String>,
+    pub exit_code: i32,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error) -> Self {
+        let message = format!("{:#}", err);
+
+        for cause in err.chain() {
+            if let Some(e) = cause.downcast_ref::<WorktyError>() {
+                return Self {
+                    kind: e.kind(),
+                    message,
+                    hint: e.hint(),
+                    exit_code: e.exit_code(),
+                };
+            }
+            if let Some(e) = cause.downcast_ref::<GitError>() {
+                let (kind, exit_code) = match e {
+                    GitError::WorktreeNotFound(_) => ("worktree_not_found", EXIT_NOT_FOUND),
+                    GitError::Dirty(_) => ("dirty", EXIT_REFUSED),
+                    GitError::Locked(_) => ("locked", EXIT_REFUSED),
+                    _ => ("git_failed", EXIT_GIT_FAILED),
+                };
+                return Self {
+                    kind,
+                    message,
+                    hint: None,
+                    exit_code,
+                };
+            }
+            if cause.downcast_ref::<git2::Error>().is_some() {
+                return Self {
+                    kind: "git_failed",
+                    message,
+                    hint: None,
+                    exit_code: EXIT_GIT_FAILED,
+                };
+            }
+        }
+
+        Self {
+            kind: "error",
+            message,
+            hint: None,
+            exit_code: EXIT_FAILURE,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonError<'a> {
+    schema_version: u32,
+    error: &'a ErrorReport,
+}
+
+/// Prints the report as `{"error": {...}}` on stdout, where `--json` consumers read.
+pub fn print_error_json(report: &ErrorReport) {
+    let output = JsonError {
+        schema_version: JSON_SCHEMA_VERSION,
+        error: report,
+    };
+    let json = serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string());
+    println!("{}", json);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_report_finds_typed_error_under_context() {
+        let err = Err::<(), _>(WorktyError::Dirty {
+            name: "feat".to_string(),
+        })
+        .context("Failed to remove worktree")
+        .unwrap_err();
+
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.kind, "dirty");
+        assert_eq!(report.exit_code, EXIT_REFUSED);
+        assert_eq!(
+            report.message,
+            "Failed to remove worktree: Worktree 'feat' has uncommitted changes"
+        );
+        assert!(report.hint.is_some());
+
+        let report = ErrorReport::new(&anyhow::anyhow!("something else"));
+        assert_eq!(report.exit_code, EXIT_FAILURE);
+        assert!(report.hint.is_none());
+    }
+}
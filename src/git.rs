@@ -1,9 +1,65 @@
+use crate::error::WorktyError;
+use crate::status_cache;
 use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 use std::sync::Mutex;
 
+const REMOTE_HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+const REMOTE_HEAD_CACHE_FILENAME: &str = "workty-remote-head.json";
+/// Cached answers older than this are still used, but asked for again in the background.
+const REMOTE_HEAD_CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+
+/// What `git ls-remote --symref` last said a remote's default branch was.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RemoteHeadEntry {
+    branch: Option<String>,
+    checked_at: i64,
+}
+
+/// Errors from worktree operations performed through libgit2.
+#[derive(Debug, thiserror::Error)]
+pub enum GitError {
+    #[error("Directory already exists: {}", .0.display())]
+    PathExists(PathBuf),
+    #[error("Branch '{0}' not found")]
+    BranchNotFound(String),
+    #[error("Branch '{0}' already exists")]
+    BranchExists(String),
+    #[error("Could not resolve '{0}' to a commit")]
+    RevisionNotFound(String),
+    #[error("No linked worktree at {}", .0.display())]
+    WorktreeNotFound(PathBuf),
+    #[error("Worktree at {} has uncommitted changes", .0.display())]
+    Dirty(PathBuf),
+    #[error("Worktree at {} is locked", .0.display())]
+    Locked(PathBuf),
+    #[error("Failed to lock repository")]
+    LockPoisoned,
+    #[error("Failed to create {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}", .0.message())]
+    Git(#[from] git2::Error),
+}
+
+/// What a new worktree should check out.
+#[derive(Debug, Clone, Copy)]
+pub enum WorktreeTarget<'a> {
+    /// An existing local branch
+    Branch(&'a str),
+    /// A branch created from `start_point`
+    NewBranch { name: &'a str, start_point: &'a str },
+    /// A detached HEAD at a revision
+    Detached(&'a str),
+}
+
 pub struct GitRepo {
     pub repo: Mutex<git2::Repository>,
     pub root: PathBuf,
@@ -25,8 +81,9 @@ impl GitRepo {
             .map(PathBuf::from)
             .unwrap_or_else(|| repo.path().to_path_buf());
 
-        // git2 path() returns the effective .git directory
-        let common_dir = repo.path().to_path_buf();
+        // commondir() is shared by all worktrees, unlike path() which points
+        // at .git/worktrees/<name> when run from a linked worktree
+        let common_dir = repo.commondir().to_path_buf();
 
         Ok(Self {
             repo: Mutex::new(repo),
@@ -44,6 +101,14 @@ impl GitRepo {
         run_git_command(Some(worktree_path), args)
     }
 
+    /// Path of the main worktree (the original clone), even when called from a linked worktree.
+    pub fn main_worktree_path(&self) -> PathBuf {
+        self.common_dir
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.root.clone())
+    }
+
     pub fn origin_url(&self) -> Option<String> {
         self.repo
             .lock()
@@ -53,7 +118,25 @@ impl GitRepo {
             .and_then(|remote| remote.url().map(|s| s.to_string()))
     }
 
-    pub fn default_branch(&self) -> Option<String> {
+    pub fn has_remote(&self, name: &str) -> bool {
+        self.repo
+            .lock()
+            .map(|repo| repo.find_remote(name).is_ok())
+            .unwrap_or(false)
+    }
+
+    /// The default branch of `remote` as recorded in `refs/remotes/<remote>/HEAD`, or
+    /// as last reported by `git ls-remote`, falling back to a local `main` or `master`.
+    /// Never waits on the network: a missing or expired answer from the remote is
+    /// fetched by a background process for later runs.
+    pub fn default_branch(&self, remote: &str) -> Option<String> {
+        if let Some(branch) = self.local_remote_head(remote) {
+            return Some(branch);
+        }
+        if let Some(branch) = self.cached_remote_head(remote) {
+            return Some(branch);
+        }
+
         const FALLBACK_BRANCHES: [&str; 2] = ["main", "master"];
         let repo = self.repo.lock().ok()?;
 
@@ -65,6 +148,124 @@ impl GitRepo {
         None
     }
 
+    /// The branch `refs/remotes/<remote>/HEAD` points at.
+    pub fn local_remote_head(&self, remote: &str) -> Option<String> {
+        let repo = self.repo.lock().ok()?;
+        let reference = repo
+            .find_reference(&format!("refs/remotes/{}/HEAD", remote))
+            .ok()?;
+        reference
+            .symbolic_target()?
+            .strip_prefix(&format!("refs/remotes/{}/", remote))
+            .map(|branch| branch.to_string())
+    }
+
+    fn cached_remote_head(&self, remote: &str) -> Option<String> {
+        if !self.has_remote(remote) {
+            return None;
+        }
+
+        let mut cache = self.read_remote_head_cache();
+        let now = crate::metadata::now_timestamp();
+        let entry = cache.get(remote).cloned();
+        let fresh = entry
+            .as_ref()
+            .is_some_and(|e| now - e.checked_at < REMOTE_HEAD_CACHE_TTL_SECS);
+        if !fresh {
+            // Marked as checked first so concurrent runs don't all start a refresh
+            cache.insert(
+                remote.to_string(),
+                RemoteHeadEntry {
+                    branch: entry.as_ref().and_then(|e| e.branch.clone()),
+                    checked_at: now,
+                },
+            );
+            self.write_remote_head_cache(&cache);
+            self.refresh_remote_head_in_background(remote);
+        }
+        entry.and_then(|e| e.branch)
+    }
+
+    /// Asks the remote for its default branch and caches the answer, failures
+    /// included, for [`GitRepo::default_branch`].
+    pub fn refresh_remote_head(&self, remote: &str) -> Option<String> {
+        let branch = self.remote_default_branch(remote);
+        let mut cache = self.read_remote_head_cache();
+        cache.insert(
+            remote.to_string(),
+            RemoteHeadEntry {
+                branch: branch.clone(),
+                checked_at: crate::metadata::now_timestamp(),
+            },
+        );
+        self.write_remote_head_cache(&cache);
+        branch
+    }
+
+    fn refresh_remote_head_in_background(&self, remote: &str) {
+        if let Ok(exe) = std::env::current_exe() {
+            let _ = Command::new(exe)
+                .arg("-C")
+                .arg(&self.root)
+                .args(["config", "--refresh-remote-head", remote])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+        }
+    }
+
+    fn read_remote_head_cache(&self) -> BTreeMap<String, RemoteHeadEntry> {
+        std::fs::read_to_string(self.common_dir.join(REMOTE_HEAD_CACHE_FILENAME))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_remote_head_cache(&self, cache: &BTreeMap<String, RemoteHeadEntry>) {
+        if let Ok(contents) = serde_json::to_string_pretty(cache) {
+            let _ = std::fs::write(self.common_dir.join(REMOTE_HEAD_CACHE_FILENAME), contents);
+        }
+    }
+
+    /// Asks the remote itself for its default branch with `git ls-remote --symref`.
+    /// This goes over the network, so it gives up after `REMOTE_HEAD_TIMEOUT`.
+    pub fn remote_default_branch(&self, remote: &str) -> Option<String> {
+        if !self.has_remote(remote) {
+            return None;
+        }
+
+        let mut child = Command::new("git")
+            .current_dir(&self.root)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .args(["ls-remote", "--symref", remote, "HEAD"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let deadline = Instant::now() + REMOTE_HEAD_TIMEOUT;
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(50))
+                }
+                _ => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return None;
+                }
+            }
+        }
+
+        let output = child.wait_with_output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_symref_head(&String::from_utf8_lossy(&output.stdout))
+    }
+
     pub fn branch_exists(&self, branch_name: &str) -> bool {
         let repo = match self.repo.lock() {
             Ok(r) => r,
@@ -76,7 +277,271 @@ impl GitRepo {
         exists
     }
 
-    pub fn is_merged(&self, branch: &str, base: &str) -> Result<bool> {
+    /// Creates a linked worktree at `path` through libgit2.
+    pub fn add_worktree(&self, path: &Path, target: WorktreeTarget) -> Result<(), GitError> {
+        if path.exists() {
+            return Err(GitError::PathExists(path.to_path_buf()));
+        }
+        // Drop anything cached for an earlier worktree at the same path
+        status_cache::invalidate(self, &[path]);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|source| GitError::Io {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+
+        let repo = self.repo.lock().map_err(|_| GitError::LockPoisoned)?;
+        let name = unique_worktree_name(&repo, path);
+
+        let branch_name = match target {
+            WorktreeTarget::Branch(branch) => branch.to_string(),
+            WorktreeTarget::NewBranch {
+                name: new_branch,
+                start_point,
+            } => {
+                if repo
+                    .find_branch(new_branch, git2::BranchType::Local)
+                    .is_ok()
+                {
+                    return Err(GitError::BranchExists(new_branch.to_string()));
+                }
+                let commit = peel_to_commit(&repo, start_point)?;
+                let mut branch = repo.branch(new_branch, &commit, false)?;
+
+                // Like `git branch`, track the start point when it is a remote branch
+                let result = if repo
+                    .find_branch(start_point, git2::BranchType::Remote)
+                    .is_ok()
+                {
+                    branch
+                        .set_upstream(Some(start_point))
+                        .map_err(GitError::from)
+                } else {
+                    Ok(())
+                }
+                .and_then(|_| add_worktree_on_branch(&repo, &name, path, branch.get()));
+
+                // Don't leave the new branch behind to block the next attempt
+                if result.is_err() {
+                    let _ = branch.delete();
+                }
+                return result;
+            }
+            WorktreeTarget::Detached(rev) => {
+                // libgit2 always checks out a branch, so check out a temporary one
+                // and detach from it afterwards
+                let commit = peel_to_commit(&repo, rev)?;
+                let temp_name = format!("workty-detached-{}", name);
+                // Never reuse a branch with that name; it might be someone's work
+                if repo
+                    .find_branch(&temp_name, git2::BranchType::Local)
+                    .is_ok()
+                {
+                    return Err(GitError::BranchExists(temp_name));
+                }
+                let temp = repo.branch(&temp_name, &commit, false)?;
+                let result =
+                    add_worktree_on_branch(&repo, &name, path, temp.get()).and_then(|_| {
+                        let wt_repo = git2::Repository::open(path)?;
+                        wt_repo.set_head_detached(commit.id())?;
+                        Ok(())
+                    });
+                repo.find_branch(&temp_name, git2::BranchType::Local)?
+                    .delete()?;
+                return result;
+            }
+        };
+
+        let branch = repo
+            .find_branch(&branch_name, git2::BranchType::Local)
+            .map_err(|_| GitError::BranchNotFound(branch_name.clone()))?;
+        add_worktree_on_branch(&repo, &name, path, branch.get())
+    }
+
+    /// Removes a linked worktree's directory and administrative files.
+    ///
+    /// Refuses dirty worktrees unless `force` is at least 1, and locked ones unless
+    /// it is at least 2, like `git worktree remove`.
+    pub fn remove_worktree(&self, path: &Path, force: u8) -> Result<(), GitError> {
+        let repo = self.repo.lock().map_err(|_| GitError::LockPoisoned)?;
+        let worktree = find_linked_worktree(&repo, path)?;
+
+        let locked = !matches!(worktree.is_locked()?, git2::WorktreeLockStatus::Unlocked);
+        if locked && force < 2 {
+            return Err(GitError::Locked(path.to_path_buf()));
+        }
+
+        if force == 0 && path.exists() {
+            let wt_repo = git2::Repository::open(path)?;
+            let mut opts = git2::StatusOptions::new();
+            opts.include_untracked(true).exclude_submodules(true);
+            if !wt_repo.statuses(Some(&mut opts))?.is_empty() {
+                return Err(GitError::Dirty(path.to_path_buf()));
+            }
+        }
+
+        worktree.prune(Some(
+            git2::WorktreePruneOptions::new()
+                .valid(true)
+                .locked(true)
+                .working_tree(true),
+        ))?;
+        drop(repo);
+
+        status_cache::invalidate(self, &[path]);
+        Ok(())
+    }
+
+    /// Relocates a linked worktree with `git worktree move`.
+    pub fn move_worktree(&self, from: &Path, to: &Path) -> Result<()> {
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let output = Command::new("git")
+            .current_dir(&self.root)
+            .arg("worktree")
+            .arg("move")
+            .arg(from)
+            .arg(to)
+            .output()
+            .context("Failed to move worktree")?;
+
+        if !output.status.success() {
+            return Err(WorktyError::GitFailed {
+                command: format!("worktree move {} {}", from.display(), to.display()),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+
+        status_cache::invalidate(self, &[from, to]);
+        Ok(())
+    }
+
+    pub fn lock_worktree(&self, path: &Path, reason: Option<&str>) -> Result<()> {
+        let wt_repo = git2::Repository::open(path)
+            .with_context(|| format!("Failed to open worktree at {}", path.display()))?;
+        let worktree =
+            git2::Worktree::open_from_repository(&wt_repo).context("Failed to open worktree")?;
+        worktree.lock(reason).context("Failed to lock worktree")
+    }
+
+    pub fn unlock_worktree(&self, path: &Path) -> Result<()> {
+        let wt_repo = git2::Repository::open(path)
+            .with_context(|| format!("Failed to open worktree at {}", path.display()))?;
+        let worktree =
+            git2::Worktree::open_from_repository(&wt_repo).context("Failed to open worktree")?;
+        worktree.unlock().context("Failed to unlock worktree")
+    }
+
+    /// Adds a worktree at `path` for `branch`, recreating the branch at `head` if it
+    /// no longer exists, or a detached worktree at `head` when there is no branch.
+    pub fn recreate_worktree(
+        &self,
+        path: &Path,
+        branch: Option<&str>,
+        head: &str,
+        upstream: Option<&(String, String)>,
+    ) -> Result<()> {
+        if self
+            .run_git(&["cat-file", "-e", &format!("{}^{{commit}}", head)])
+            .is_err()
+        {
+            bail!(
+                "Commit {} is missing from this repository (it may have been garbage collected)",
+                head
+            );
+        }
+
+        let branch = match branch {
+            Some(branch) => branch,
+            None => {
+                self.add_worktree(path, WorktreeTarget::Detached(head))?;
+                return Ok(());
+            }
+        };
+
+        if self.branch_exists(branch) {
+            let tip = self.run_git(&["rev-parse", &format!("refs/heads/{}", branch)])?;
+            if tip.trim() != head {
+                bail!(
+                    "Branch '{}' already exists at a different commit. Rename or delete it before restoring.",
+                    branch
+                );
+            }
+        } else {
+            self.run_git(&["branch", branch, head])?;
+        }
+
+        self.add_worktree(path, WorktreeTarget::Branch(branch))?;
+
+        if let Some((remote, merge)) = upstream {
+            if self.branch_upstream_config(branch).is_none() {
+                self.run_git(&["config", &format!("branch.{}.remote", branch), remote])?;
+                self.run_git(&["config", &format!("branch.{}.merge", branch), merge])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renames a local branch, keeping its upstream tracking configuration.
+    pub fn rename_branch(&self, old: &str, new: &str) -> Result<()> {
+        let upstream = self.branch_upstream_config(old);
+
+        self.run_git(&["branch", "-m", old, new])
+            .context("Failed to rename branch")?;
+
+        if let Some((remote, merge)) = upstream {
+            if self.branch_upstream_config(new).is_none() {
+                let repo = self
+                    .repo
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("Failed to lock repository"))?;
+                let mut config = repo.config().context("Failed to open git config")?;
+                config.set_str(&format!("branch.{}.remote", new), &remote)?;
+                config.set_str(&format!("branch.{}.merge", new), &merge)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a single value from git config (e.g. `user.name`).
+    pub fn config_value(&self, key: &str) -> Option<String> {
+        let repo = self.repo.lock().ok()?;
+        let config = repo.config().ok()?.snapshot().ok()?;
+        config.get_string(key).ok()
+    }
+
+    /// Returns the `branch.<name>.remote` and `branch.<name>.merge` config values.
+    pub fn branch_upstream_config(&self, branch: &str) -> Option<(String, String)> {
+        let repo = self.repo.lock().ok()?;
+        let config = repo.config().ok()?.snapshot().ok()?;
+        let remote = config
+            .get_string(&format!("branch.{}.remote", branch))
+            .ok()?;
+        let merge = config
+            .get_string(&format!("branch.{}.merge", branch))
+            .ok()?;
+        Some((remote, merge))
+    }
+
+    pub fn is_merged(&self, branch: &str, base: &str, remote: &str) -> Result<bool> {
+        Ok(self.merge_method(branch, base, remote)?.is_some())
+    }
+
+    /// Checks whether `branch` has landed on the local `base` or `<remote>/<base>`,
+    /// including through rebase- and squash-merges that rewrite its commits.
+    pub fn merge_method(
+        &self,
+        branch: &str,
+        base: &str,
+        remote: &str,
+    ) -> Result<Option<MergeMethod>> {
         let repo = self
             .repo
             .lock()
@@ -84,28 +549,218 @@ impl GitRepo {
 
         let branch_oid = match repo.revparse_single(branch) {
             Ok(obj) => obj.id(),
-            Err(_) => return Ok(false),
+            Err(_) => return Ok(None),
         };
 
-        // 1. Check against local base
-        if let Ok(base_obj) = repo.revparse_single(base) {
-            if let Ok(true) = repo.graph_descendant_of(base_obj.id(), branch_oid) {
-                return Ok(true);
+        let remote_base = format!("{}/{}", remote, base);
+        let base_oids: Vec<git2::Oid> = [base, remote_base.as_str()]
+            .iter()
+            .filter_map(|name| repo.revparse_single(name).ok())
+            .filter_map(|obj| obj.peel_to_commit().ok())
+            .map(|commit| commit.id())
+            .collect();
+
+        // Cheap check first, against every candidate base. A branch still at the
+        // base commit has nothing of its own to merge, so it doesn't count.
+        for &base_oid in &base_oids {
+            if matches!(repo.graph_descendant_of(base_oid, branch_oid), Ok(true)) {
+                return Ok(Some(MergeMethod::Ancestor));
             }
         }
 
-        // 2. Check against remote base (e.g. origin/main)
-        let remote_base = format!("origin/{}", base);
-        if let Ok(remote_base_obj) = repo.revparse_single(&remote_base) {
-            if let Ok(true) = repo.graph_descendant_of(remote_base_obj.id(), branch_oid) {
-                return Ok(true);
+        for &base_oid in &base_oids {
+            if let Some(method) = rewritten_merge_method(&repo, branch_oid, base_oid)? {
+                return Ok(Some(method));
             }
         }
 
-        Ok(false)
+        Ok(None)
     }
 }
 
+/// How a branch was found to be merged into its base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeMethod {
+    /// The branch tip is reachable from base (fast-forward or merge commit)
+    Ancestor,
+    /// Every commit on the branch was rebased or cherry-picked onto base
+    PatchEquivalent,
+    /// The branch's combined changes landed on base as a single commit
+    Squash,
+}
+
+impl std::fmt::Display for MergeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeMethod::Ancestor => write!(f, "ancestor"),
+            MergeMethod::PatchEquivalent => write!(f, "patch-equivalent"),
+            MergeMethod::Squash => write!(f, "squash"),
+        }
+    }
+}
+
+/// Upper bound on base commits scanned for matching patches, to keep `clean` fast
+/// on long-lived branches.
+const MAX_BASE_COMMITS_SCANNED: usize = 2000;
+
+fn rewritten_merge_method(
+    repo: &git2::Repository,
+    branch_oid: git2::Oid,
+    base_oid: git2::Oid,
+) -> Result<Option<MergeMethod>> {
+    let merge_base = match repo.merge_base(branch_oid, base_oid) {
+        Ok(oid) => oid,
+        Err(_) => return Ok(None),
+    };
+
+    let branch_commits = commits_between(repo, branch_oid, merge_base, usize::MAX)?;
+    let base_commits = commits_between(repo, base_oid, merge_base, MAX_BASE_COMMITS_SCANNED)?;
+
+    let base_patch_ids: std::collections::HashSet<git2::Oid> = base_commits
+        .iter()
+        .filter_map(|&oid| commit_patch_id(repo, oid))
+        .collect();
+
+    // Rebase-merge or cherry-pick: each branch commit has an identical patch on base
+    let branch_patch_ids: Vec<Option<git2::Oid>> = branch_commits
+        .iter()
+        .map(|&oid| commit_patch_id(repo, oid))
+        .collect();
+    if branch_patch_ids.iter().any(|id| id.is_some())
+        && branch_patch_ids
+            .iter()
+            .flatten()
+            .all(|id| base_patch_ids.contains(id))
+    {
+        return Ok(Some(MergeMethod::PatchEquivalent));
+    }
+
+    // Squash-merge: the branch's combined diff shows up as one commit on base
+    let merge_base_tree = repo.find_commit(merge_base)?.tree()?;
+    let branch_tree = repo.find_commit(branch_oid)?.tree()?;
+    let combined = repo.diff_tree_to_tree(Some(&merge_base_tree), Some(&branch_tree), None)?;
+    if combined.deltas().len() == 0 {
+        return Ok(None);
+    }
+    if let Ok(combined_id) = combined.patchid(None) {
+        if base_patch_ids.contains(&combined_id) {
+            return Ok(Some(MergeMethod::Squash));
+        }
+    }
+
+    // Squash-merge followed by further changes to the same files: merging the
+    // branch into base again would not change base's tree
+    let base_tree = repo.find_commit(base_oid)?.tree()?;
+    let mut index = repo.merge_trees(&merge_base_tree, &base_tree, &branch_tree, None)?;
+    if !index.has_conflicts() && index.write_tree_to(repo)? == base_tree.id() {
+        return Ok(Some(MergeMethod::Squash));
+    }
+
+    Ok(None)
+}
+
+/// Non-merge commits reachable from `tip` but not from `stop`, newest first.
+fn commits_between(
+    repo: &git2::Repository,
+    tip: git2::Oid,
+    stop: git2::Oid,
+    limit: usize,
+) -> Result<Vec<git2::Oid>> {
+    let mut walk = repo.revwalk()?;
+    walk.push(tip)?;
+    walk.hide(stop)?;
+
+    let mut commits = Vec::new();
+    for oid in walk.take(limit) {
+        let oid = oid?;
+        if repo.find_commit(oid)?.parent_count() == 1 {
+            commits.push(oid);
+        }
+    }
+    Ok(commits)
+}
+
+/// Stable identifier for the change a commit introduces, like `git patch-id`.
+fn commit_patch_id(repo: &git2::Repository, oid: git2::Oid) -> Option<git2::Oid> {
+    let commit = repo.find_commit(oid).ok()?;
+    let parent_tree = commit.parent(0).ok()?.tree().ok()?;
+    let tree = commit.tree().ok()?;
+    let diff = repo
+        .diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)
+        .ok()?;
+    if diff.deltas().len() == 0 {
+        return None;
+    }
+    diff.patchid(None).ok()
+}
+
+fn add_worktree_on_branch(
+    repo: &git2::Repository,
+    name: &str,
+    path: &Path,
+    reference: &git2::Reference,
+) -> Result<(), GitError> {
+    let mut opts = git2::WorktreeAddOptions::new();
+    opts.reference(Some(reference));
+    repo.worktree(name, path, Some(&opts))?;
+    Ok(())
+}
+
+fn peel_to_commit<'r>(repo: &'r git2::Repository, rev: &str) -> Result<git2::Commit<'r>, GitError> {
+    repo.revparse_single(rev)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|_| GitError::RevisionNotFound(rev.to_string()))
+}
+
+/// Administrative name for a new worktree: its directory name, suffixed with a
+/// number if `.git/worktrees/<name>` is already taken.
+fn unique_worktree_name(repo: &git2::Repository, path: &Path) -> String {
+    let base = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "worktree".to_string());
+
+    let admin_dir = repo.commondir().join("worktrees");
+    let mut name = base.clone();
+    let mut n = 1;
+    while admin_dir.join(&name).exists() {
+        name = format!("{}{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+fn find_linked_worktree(repo: &git2::Repository, path: &Path) -> Result<git2::Worktree, GitError> {
+    let wanted = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    for name in repo.worktrees()?.iter().flatten() {
+        if let Ok(worktree) = repo.find_worktree(name) {
+            let wt_path = worktree.path();
+            let wt_path = wt_path
+                .canonicalize()
+                .unwrap_or_else(|_| wt_path.to_path_buf());
+            if wt_path == wanted {
+                return Ok(worktree);
+            }
+        }
+    }
+
+    Err(GitError::WorktreeNotFound(path.to_path_buf()))
+}
+
+/// Extracts the branch from `git ls-remote --symref <remote> HEAD` output.
+fn parse_symref_head(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (target, name) = line.strip_prefix("ref: ")?.split_once('\t')?;
+        if name != "HEAD" {
+            return None;
+        }
+        target.strip_prefix("refs/heads/").map(|b| b.to_string())
+    })
+}
+
 pub fn run_git_command(working_directory: Option<&Path>, args: &[&str]) -> Result<String> {
     let mut cmd = Command::new("git");
     if let Some(directory) = working_directory {
@@ -117,7 +772,11 @@ pub fn run_git_command(working_directory: Option<&Path>, args: &[&str]) -> Resul
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git {} failed: {}", args.join(" "), stderr.trim());
+        return Err(WorktyError::GitFailed {
+            command: args.join(" "),
+            stderr: stderr.trim().to_string(),
+        }
+        .into());
     }
 
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
@@ -139,3 +798,75 @@ pub fn is_in_git_repo(path: &Path) -> bool {
         .map(|o| o.status.success())
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_symref_head() {
+        let output = "ref: refs/heads/develop\tHEAD\n1234567890abcdef\tHEAD\n";
+        assert_eq!(parse_symref_head(output), Some("develop".to_string()));
+        assert_eq!(parse_symref_head("1234567890abcdef\tHEAD\n"), None);
+        assert_eq!(parse_symref_head(""), None);
+    }
+
+    #[test]
+    fn test_detached_worktree_leaves_existing_temp_branch_alone() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(temp.path())
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&["init", "-q", "-b", "main"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "one"]);
+        git(&["branch", "workty-detached-wt"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "two"]);
+        let tip = git(&["rev-parse", "workty-detached-wt"]);
+
+        let repo = GitRepo::discover(Some(temp.path())).unwrap();
+        let path = temp.path().join("wt");
+        let err = repo
+            .add_worktree(&path, WorktreeTarget::Detached("HEAD"))
+            .unwrap_err();
+
+        assert!(matches!(err, GitError::BranchExists(_)));
+        assert_eq!(git(&["rev-parse", "workty-detached-wt"]), tip);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_add_removes_the_new_branch() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(temp.path())
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "-q", "-b", "main"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "one"]);
+
+        // A dangling symlink doesn't "exist", but libgit2 can't create the directory
+        let path = temp.path().join("wt");
+        std::os::unix::fs::symlink(temp.path().join("missing"), &path).unwrap();
+
+        let repo = GitRepo::discover(Some(temp.path())).unwrap();
+        let target = WorktreeTarget::NewBranch {
+            name: "feat",
+            start_point: "main",
+        };
+        assert!(repo.add_worktree(&path, target).is_err());
+        assert!(!repo.branch_exists("feat"));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::config::{self, Config, HookFailure};
+use crate::ui::{print_info, print_warning};
+use anyhow::{bail, Context, Result};
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PostCreate,
+    PreRemove,
+    PostSwitch,
+}
+
+impl Hook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::PostCreate => "post_create",
+            Hook::PreRemove => "pre_remove",
+            Hook::PostSwitch => "post_switch",
+        }
+    }
+
+    fn commands<'a>(&self, config: &'a Config) -> &'a [String] {
+        match self {
+            Hook::PostCreate => &config.hooks.post_create,
+            Hook::PreRemove => &config.hooks.pre_remove,
+            Hook::PostSwitch => &config.hooks.post_switch,
+        }
+    }
+}
+
+/// Set to the hook's name while it runs.
+pub const HOOK_VAR: &str = "WORKTY_HOOK";
+/// Variables describing the worktree. `WORKTY_BASE` doubles as a config override
+/// key, so config loading ignores these while [`HOOK_VAR`] is set.
+pub const CONTEXT_VARS: &[&str] = &["WORKTY_PATH", "WORKTY_REPO", "WORKTY_BRANCH", "WORKTY_BASE"];
+
+/// Values exposed to hook commands as `WORKTY_*` environment variables.
+pub struct HookContext<'a> {
+    pub worktree_path: &'a Path,
+    pub branch: Option<&'a str>,
+    pub base: Option<&'a str>,
+    pub repo_root: &'a Path,
+}
+
+/// Runs every command configured for `hook` inside the worktree.
+///
+/// Output is streamed to stderr so that stdout stays usable for `--print-path`.
+/// A failing command either aborts with an error or prints a warning,
+/// depending on `hooks.on_failure`.
+pub fn run_hook(config: &Config, hook: Hook, ctx: &HookContext) -> Result<()> {
+    for command in hook.commands(config) {
+        print_info(&format!("Running {} hook: {}", hook.name(), command));
+
+        if let Err(e) = run_command(hook, command, ctx) {
+            match config.hooks.on_failure {
+                HookFailure::Abort => return Err(e),
+                HookFailure::Warn => print_warning(&format!("{:#}", e)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_command(hook: Hook, command: &str, ctx: &HookContext) -> Result<()> {
+    let mut cmd = shell_command(command);
+    // Overrides given to this run shouldn't leak into a `git workty` the hook runs
+    for var in config::env_override_vars() {
+        cmd.env_remove(var);
+    }
+    cmd.current_dir(ctx.worktree_path)
+        .env(HOOK_VAR, hook.name())
+        .env("WORKTY_PATH", ctx.worktree_path)
+        .env("WORKTY_REPO", ctx.repo_root)
+        .env("WORKTY_BRANCH", ctx.branch.unwrap_or_default())
+        .env("WORKTY_BASE", ctx.base.unwrap_or_default())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run {} hook `{}`", hook.name(), command))?;
+
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = io::copy(&mut stdout, &mut io::stderr());
+    }
+
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to wait for {} hook `{}`", hook.name(), command))?;
+
+    if !status.success() {
+        bail!(
+            "{} hook `{}` failed ({})",
+            hook.name(),
+            command,
+            status
+                .code()
+                .map(|c| format!("exit code {}", c))
+                .unwrap_or_else(|| "terminated by signal".to_string())
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
@@ -1,19 +1,31 @@
+pub mod archive;
 pub mod commands;
 pub mod config;
+pub mod copy;
+pub mod daemon;
+pub mod error;
 pub mod gh;
 pub mod git;
+pub mod hooks;
+pub mod metadata;
+pub mod oplog;
 pub mod shell;
 pub mod status;
+pub mod status_cache;
 pub mod ui;
 pub mod worktree;
 
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::commands::sync::SyncStrategy;
 use crate::commands::{
-    clean, completions, doctor, fetch, go, init, install_man, list, new, pick, pr, rm, sync,
+    clean, completions, config as config_cmd, dashboard, doctor, fetch, go, init, install_man,
+    list, lock, mv, new, note, pick, pr, prompt, restore, rm, sync, undo, unlock, watch,
 };
+use crate::error::ErrorReport;
 use crate::git::GitRepo;
 use crate::ui::UiOptions;
 
@@ -38,7 +50,18 @@ SHELL INTEGRATION:
     This provides:
         wcd   - fuzzy select and cd to a worktree
         wnew  - create new worktree and cd into it
-        wgo   - go to a worktree by name";
+        wgo   - go to a worktree by name
+
+EXIT CODES:
+    0    Success
+    1    Other error
+    2    Invalid arguments
+    3    Worktree not found, or the name matches several
+    4    Refused: worktree is dirty, locked or mid-rebase/merge
+    5    A git command failed
+    6    GitHub CLI (gh) missing or not authenticated
+    7    Declined a confirmation prompt
+    130  Cancelled a picker";
 
 #[derive(Parser)]
 #[command(name = "git-workty", bin_name = "git workty")]
@@ -77,13 +100,23 @@ pub enum Commands {
         /// Skip dirty file check for faster output
         #[arg(long)]
         fast: bool,
+
+        /// Show statuses cached by earlier runs, refreshing them in the background
+        #[arg(long, conflicts_with = "fast")]
+        cached: bool,
+
+        /// Recompute the status cache without printing anything
+        #[arg(long, hide = true)]
+        refresh_cache: bool,
     },
 
     /// Create a new workspace
     #[command(after_help = "EXAMPLES:
     git workty new feat/login
     git workty new hotfix --from main
-    git workty new feature --no-fetch --no-push")]
+    git workty new feature --no-fetch --no-push
+    git workty new login --type feat --ticket ABC-123
+    git workty new scratch --no-template")]
     New {
         /// Branch name for the new workspace
         name: String,
@@ -111,6 +144,52 @@ pub enum Commands {
         /// Skip pushing to set upstream after creating
         #[arg(long)]
         no_push: bool,
+
+        /// Skip copying/linking the configured `copy` and `link` files
+        #[arg(long)]
+        no_copy: bool,
+
+        /// Free-text note to attach to the worktree
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Ticket ID to link to the worktree (also fills `{ticket}` in branch_template)
+        #[arg(long)]
+        ticket: Option<String>,
+
+        /// Branch type for `{type}` in branch_template (e.g. feat, fix)
+        #[arg(long = "type", value_name = "TYPE")]
+        branch_type: Option<String>,
+
+        /// Use NAME as the branch name verbatim, ignoring branch_template
+        #[arg(long)]
+        no_template: bool,
+    },
+
+    /// Show or edit the note attached to a worktree
+    #[command(after_help = "EXAMPLES:
+    git workty note feat/login \"waiting on API review\"
+    git workty note feat/login --ticket ABC-123
+    git workty note feat/login --base upstream/release-2.0
+    git workty note feat/login --clear")]
+    Note {
+        /// Worktree name
+        name: String,
+
+        /// Note text (prints the current note if omitted)
+        text: Option<String>,
+
+        /// Ticket ID to link to the worktree
+        #[arg(long)]
+        ticket: Option<String>,
+
+        /// Compare this worktree against another base branch (e.g. upstream/develop)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Remove the note, ticket and base override
+        #[arg(long)]
+        clear: bool,
     },
 
     /// Print path to a worktree by name
@@ -127,6 +206,17 @@ pub enum Commands {
     cd \"$(git workty pick)\"")]
     Pick,
 
+    /// Full-screen dashboard with live status and key-bound actions
+    #[command(after_help = "EXAMPLES:
+    git workty ui
+    cd \"$(git workty ui)\"
+
+KEYS:
+    enter  print the selected path and exit (use `wui` to cd)
+    d      remove    s  sync    f  fetch
+    o      open in open_cmd     /  filter    q  quit")]
+    Ui,
+
     /// Remove a workspace
     #[command(after_help = "EXAMPLES:
     git workty rm feat/login
@@ -136,13 +226,36 @@ pub enum Commands {
         /// Worktree name to remove
         name: String,
 
-        /// Remove even if worktree has uncommitted changes
-        #[arg(long, short = 'f')]
-        force: bool,
+        /// Remove even if worktree has uncommitted changes or a rebase, merge, etc. in progress (twice to also remove a locked worktree)
+        #[arg(long, short = 'f', action = clap::ArgAction::Count)]
+        force: u8,
 
         /// Also delete the branch after removing worktree
         #[arg(long, short = 'd')]
         delete_branch: bool,
+
+        /// Save unpushed commits and uncommitted changes first (see `restore`)
+        #[arg(long, overrides_with = "no_archive")]
+        archive: bool,
+
+        /// Don't archive, even if `archive = true` is configured
+        #[arg(long, overrides_with = "archive")]
+        no_archive: bool,
+    },
+
+    /// Rename a worktree's branch and move its directory to match
+    #[command(after_help = "EXAMPLES:
+    git workty mv feat/login feat/sign-in")]
+    Mv {
+        /// Worktree to rename
+        old: String,
+
+        /// New branch name
+        new: String,
+
+        /// Move even if a rebase, merge, etc. is in progress
+        #[arg(long, short = 'f')]
+        force: bool,
     },
 
     /// Remove merged or stale worktrees
@@ -151,7 +264,7 @@ pub enum Commands {
     git workty clean --gone --yes
     git workty clean --stale 30")]
     Clean {
-        /// Remove worktrees whose branch is merged into base
+        /// Remove worktrees whose branch is merged into base (including squash and rebase merges)
         #[arg(long)]
         merged: bool,
 
@@ -166,6 +279,53 @@ pub enum Commands {
         /// Show what would be removed without removing
         #[arg(long, short = 'n')]
         dry_run: bool,
+
+        /// Also remove worktrees with a rebase, merge, etc. in progress (twice to also remove locked ones)
+        #[arg(long, short = 'f', action = clap::ArgAction::Count)]
+        force: u8,
+
+        /// Save unpushed commits and uncommitted changes first (see `restore`)
+        #[arg(long, overrides_with = "no_archive")]
+        archive: bool,
+
+        /// Don't archive, even if `archive = true` is configured
+        #[arg(long, overrides_with = "archive")]
+        no_archive: bool,
+    },
+
+    /// Lock a worktree so it is never cleaned up or removed by accident
+    #[command(after_help = "EXAMPLES:
+    git workty lock feat/login
+    git workty lock feat/login --reason \"long-running experiment\"")]
+    Lock {
+        /// Worktree name
+        name: String,
+
+        /// Why the worktree is locked
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// Unlock a previously locked worktree
+    Unlock {
+        /// Worktree name
+        name: String,
+    },
+
+    /// Bring back the worktrees removed by the last `rm` or `clean`
+    #[command(after_help = "EXAMPLES:
+    git workty clean --merged --yes
+    git workty undo")]
+    Undo,
+
+    /// Recreate a worktree removed with --archive (lists archives if no name is given)
+    #[command(after_help = "EXAMPLES:
+    git workty rm feat/login --force --archive
+    git workty restore
+    git workty restore feat/login")]
+    Restore {
+        /// Archive directory, or the name of the archived worktree (newest wins)
+        archive: Option<String>,
     },
 
     /// Print shell integration script
@@ -185,6 +345,27 @@ pub enum Commands {
         no_cd: bool,
     },
 
+    /// Show or change configuration
+    #[command(after_help = "EXAMPLES:
+    git workty config --show-origin
+    git workty config get base
+    git workty config set base develop
+    git workty config set --user open_cmd code
+    git workty config unset hooks.on_failure
+    git workty config edit")]
+    Config {
+        /// Show which file or variable each value came from
+        #[arg(long)]
+        show_origin: bool,
+
+        /// Ask REMOTE for its default branch and cache it (run in the background)
+        #[arg(long, hide = true, value_name = "REMOTE")]
+        refresh_remote_head: Option<String>,
+
+        #[command(subcommand)]
+        action: Option<ConfigCommand>,
+    },
+
     /// Diagnose common issues
     Doctor,
 
@@ -212,6 +393,10 @@ pub enum Commands {
         /// Open the worktree in configured editor
         #[arg(long, short = 'o')]
         open: bool,
+
+        /// Skip copying/linking the configured `copy` and `link` files
+        #[arg(long)]
+        no_copy: bool,
     },
 
     /// Fetch from remotes (updates tracking info for all worktrees)
@@ -224,24 +409,121 @@ pub enum Commands {
         all: bool,
     },
 
-    /// Rebase all clean worktrees onto their upstream
+    /// Update all clean worktrees from their upstream (or the base)
     #[command(after_help = "EXAMPLES:
     git workty sync --dry-run
-    git workty sync --fetch")]
+    git workty sync --fetch
+    git workty sync --strategy ff-only
+    git workty sync --onto-base --jobs 8")]
     Sync {
         /// Show what would be done without doing it
         #[arg(long, short = 'n')]
         dry_run: bool,
 
-        /// Fetch from origin before syncing
+        /// Fetch from the configured remote before syncing
         #[arg(long, short = 'f')]
         fetch: bool,
+
+        /// How to bring branches up to date
+        #[arg(long, value_enum, default_value_t = SyncStrategy::Rebase)]
+        strategy: SyncStrategy,
+
+        /// Sync onto the configured base instead of each branch's upstream
+        #[arg(long)]
+        onto_base: bool,
+
+        /// Number of worktrees to sync at once (default 4)
+        #[arg(long, short = 'j', value_name = "N")]
+        jobs: Option<usize>,
+
+        /// Try worktrees with a rebase, merge, etc. in progress; they're reported as
+        /// failed, never aborted
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print a status segment for the current worktree, for shell prompts
+    #[command(after_help = "EXAMPLES:
+    git workty prompt
+    git workty prompt --format '[{name}{dirty}] {behind}'
+    PROMPT='$(git workty prompt) %~ %# '
+
+PLACEHOLDERS:
+    {name}    Worktree name
+    {dirty}   Marker when there are uncommitted changes
+    {ahead}   Commits ahead of upstream
+    {behind}  Commits behind upstream
+    {rebase}  Commits behind the base, when it needs a rebase
+
+Prints nothing outside a git repository.")]
+    Prompt {
+        /// Segment layout; empty placeholders collapse and trailing spaces are trimmed
+        #[arg(long, default_value = prompt::DEFAULT_FORMAT)]
+        format: String,
+
+        /// Give up on slower status checks after this many milliseconds
+        #[arg(long, value_name = "MS", default_value_t = prompt::DEFAULT_TIMEOUT_MS)]
+        timeout_ms: u64,
+    },
+
+    /// Keep worktree statuses fresh in the background for `list` and `pick`
+    #[command(after_help = "EXAMPLES:
+    git workty watch &
+    git workty watch --status
+    git workty watch --stop")]
+    Watch {
+        /// Report whether a watcher is running for this repository
+        #[arg(long, conflicts_with = "stop")]
+        status: bool,
+
+        /// Stop the running watcher
+        #[arg(long)]
+        stop: bool,
     },
 
     /// Install manpage to ~/.local/share/man/man1
     InstallMan,
 }
 
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective value of a key
+    Get {
+        /// Dotted key, e.g. `base` or `hooks.on_failure`
+        key: String,
+    },
+
+    /// Set a key in the repo (or user) config file
+    Set {
+        /// Dotted key, e.g. `base` or `hooks.on_failure`
+        key: String,
+
+        /// TOML value; bare words are treated as strings
+        value: String,
+
+        /// Write to ~/.config/workty/workty.toml instead of .git/workty.toml
+        #[arg(long)]
+        user: bool,
+    },
+
+    /// Remove a key from the repo (or user) config file
+    Unset {
+        /// Dotted key, e.g. `base` or `hooks.on_failure`
+        key: String,
+
+        /// Edit ~/.config/workty/workty.toml instead of .git/workty.toml
+        #[arg(long)]
+        user: bool,
+    },
+
+    /// Open the repo (or user) config file in $EDITOR
+    Edit {
+        /// Edit ~/.config/workty/workty.toml instead of .git/workty.toml
+        #[arg(long)]
+        user: bool,
+    },
+}
+
 pub fn run_cli() {
     let cli = Cli::parse();
 
@@ -254,8 +536,13 @@ pub fn run_cli() {
     let result = run(cli, &ui_opts);
 
     if let Err(e) = result {
-        ui::print_error(&format!("{:#}", e), None);
-        std::process::exit(1);
+        let report = ErrorReport::new(&e);
+        if ui_opts.json {
+            error::print_error_json(&report);
+        } else {
+            ui::print_error(&report.message, report.hint.as_deref());
+        }
+        std::process::exit(report.exit_code);
     }
 }
 
@@ -265,12 +552,20 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
     match cli.command {
         None => {
             let repo = GitRepo::discover(start_path)?;
-            list::execute(&repo, ui_opts, false)
+            list::execute(&repo, ui_opts, false, false)
         }
 
-        Some(Commands::List { fast }) => {
+        Some(Commands::List {
+            fast,
+            cached,
+            refresh_cache,
+        }) => {
             let repo = GitRepo::discover(start_path)?;
-            list::execute(&repo, ui_opts, fast)
+            if refresh_cache {
+                list::refresh_cache(&repo)
+            } else {
+                list::execute(&repo, ui_opts, fast, cached)
+            }
         }
 
         Some(Commands::New {
@@ -281,6 +576,11 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
             open,
             no_fetch,
             no_push,
+            no_copy,
+            note,
+            ticket,
+            branch_type,
+            no_template,
         }) => {
             let repo = GitRepo::discover(start_path)?;
             new::execute(
@@ -293,13 +593,39 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
                     open,
                     no_fetch,
                     no_push,
+                    no_copy,
+                    note,
+                    ticket,
+                    branch_type,
+                    no_template,
+                    json: ui_opts.json,
+                },
+            )
+        }
+
+        Some(Commands::Note {
+            name,
+            text,
+            ticket,
+            base,
+            clear,
+        }) => {
+            let repo = GitRepo::discover(start_path)?;
+            note::execute(
+                &repo,
+                note::NoteOptions {
+                    name,
+                    text,
+                    ticket,
+                    base,
+                    clear,
                 },
             )
         }
 
         Some(Commands::Go { name }) => {
             let repo = GitRepo::discover(start_path)?;
-            go::execute(&repo, &name)
+            go::execute(&repo, &name, ui_opts.json)
         }
 
         Some(Commands::Pick) => {
@@ -307,10 +633,17 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
             pick::execute(&repo, ui_opts)
         }
 
+        Some(Commands::Ui) => {
+            let repo = GitRepo::discover(start_path)?;
+            dashboard::execute(&repo, ui_opts)
+        }
+
         Some(Commands::Rm {
             name,
             force,
             delete_branch,
+            archive,
+            no_archive,
         }) => {
             let repo = GitRepo::discover(start_path)?;
             rm::execute(
@@ -320,15 +653,25 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
                     force,
                     delete_branch,
                     yes: cli.yes,
+                    archive: archive_flag(archive, no_archive),
+                    json: ui_opts.json,
                 },
             )
         }
 
+        Some(Commands::Mv { old, new, force }) => {
+            let repo = GitRepo::discover(start_path)?;
+            mv::execute(&repo, mv::MvOptions { old, new, force })
+        }
+
         Some(Commands::Clean {
             merged,
             gone,
             stale,
             dry_run,
+            force,
+            archive,
+            no_archive,
         }) => {
             let repo = GitRepo::discover(start_path)?;
             clean::execute(
@@ -338,11 +681,34 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
                     gone,
                     stale_days: stale,
                     dry_run,
+                    force,
                     yes: cli.yes,
+                    json: ui_opts.json,
+                    archive: archive_flag(archive, no_archive),
                 },
             )
         }
 
+        Some(Commands::Lock { name, reason }) => {
+            let repo = GitRepo::discover(start_path)?;
+            lock::execute(&repo, &name, reason.as_deref())
+        }
+
+        Some(Commands::Unlock { name }) => {
+            let repo = GitRepo::discover(start_path)?;
+            unlock::execute(&repo, &name)
+        }
+
+        Some(Commands::Undo) => {
+            let repo = GitRepo::discover(start_path)?;
+            undo::execute(&repo)
+        }
+
+        Some(Commands::Restore { archive }) => {
+            let repo = GitRepo::discover(start_path)?;
+            restore::execute(&repo, archive.as_deref())
+        }
+
         Some(Commands::Init {
             shell,
             wrap_git,
@@ -356,8 +722,32 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
             Ok(())
         }
 
+        Some(Commands::Config {
+            show_origin,
+            refresh_remote_head,
+            action,
+        }) => {
+            let repo = GitRepo::discover(start_path)?;
+            if let Some(remote) = refresh_remote_head {
+                repo.refresh_remote_head(&remote);
+                return Ok(());
+            }
+            let (action, user) = match action {
+                None => (config_cmd::ConfigAction::List { show_origin }, false),
+                Some(ConfigCommand::Get { key }) => (config_cmd::ConfigAction::Get { key }, false),
+                Some(ConfigCommand::Set { key, value, user }) => {
+                    (config_cmd::ConfigAction::Set { key, value }, user)
+                }
+                Some(ConfigCommand::Unset { key, user }) => {
+                    (config_cmd::ConfigAction::Unset { key }, user)
+                }
+                Some(ConfigCommand::Edit { user }) => (config_cmd::ConfigAction::Edit, user),
+            };
+            config_cmd::execute(&repo, config_cmd::ConfigOptions { action, user })
+        }
+
         Some(Commands::Doctor) => {
-            doctor::execute(start_path);
+            doctor::execute(start_path, cli.yes, ui_opts.json);
             Ok(())
         }
 
@@ -370,6 +760,7 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
             number,
             print_path,
             open,
+            no_copy,
         }) => {
             let repo = GitRepo::discover(start_path)?;
             pr::execute(
@@ -378,24 +769,72 @@ fn run(cli: Cli, ui_opts: &UiOptions) -> anyhow::Result<()> {
                     number,
                     print_path,
                     open,
+                    no_copy,
+                    json: ui_opts.json,
                 },
             )
         }
 
         Some(Commands::Fetch { all }) => {
             let repo = GitRepo::discover(start_path)?;
-            fetch::execute(&repo, all)
+            fetch::execute(&repo, all, ui_opts.json)
         }
 
-        Some(Commands::Sync { dry_run, fetch }) => {
+        Some(Commands::Sync {
+            dry_run,
+            fetch,
+            strategy,
+            onto_base,
+            jobs,
+            force,
+        }) => {
+            let repo = GitRepo::discover(start_path)?;
+            sync::execute(
+                &repo,
+                sync::SyncOptions {
+                    dry_run,
+                    fetch,
+                    json: ui_opts.json,
+                    strategy,
+                    onto_base,
+                    jobs,
+                    force,
+                },
+            )
+        }
+
+        Some(Commands::Prompt { format, timeout_ms }) => {
+            prompt::execute(
+                start_path,
+                &prompt::PromptOptions {
+                    format,
+                    timeout: Duration::from_millis(timeout_ms),
+                },
+                ui_opts,
+            );
+            Ok(())
+        }
+
+        Some(Commands::Watch { status, stop }) => {
             let repo = GitRepo::discover(start_path)?;
-            sync::execute(&repo, sync::SyncOptions { dry_run, fetch })
+            watch::execute(&repo, watch::WatchOptions { status, stop })
         }
 
         Some(Commands::InstallMan) => install_man::execute(cli.yes),
     }
 }
 
+/// `--archive` / `--no-archive`, or `None` to use the config default.
+fn archive_flag(archive: bool, no_archive: bool) -> Option<bool> {
+    if archive {
+        Some(true)
+    } else if no_archive {
+        Some(false)
+    } else {
+        None
+    }
+}
+
 fn supports_color() -> bool {
     use is_terminal::IsTerminal;
 
@@ -0,0 +1,128 @@
+use crate::git::GitRepo;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const METADATA_FILENAME: &str = "workty-metadata.json";
+
+/// Information workty records about a worktree that git itself doesn't track.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorktreeMetadata {
+    /// Unix timestamp of when the worktree was created by workty
+    pub created_at: Option<i64>,
+    /// Branch or ref the worktree was forked from
+    pub created_from: Option<String>,
+    /// Base set with `note --base`, used instead of the configured one
+    pub base: Option<String>,
+    pub note: Option<String>,
+    pub ticket: Option<String>,
+}
+
+impl WorktreeMetadata {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Per-repository metadata, keyed by worktree path and stored under the common git dir.
+#[derive(Debug, Default)]
+pub struct MetadataStore {
+    path: PathBuf,
+    entries: BTreeMap<String, WorktreeMetadata>,
+}
+
+impl MetadataStore {
+    pub fn load(repo: &GitRepo) -> Result<Self> {
+        let path = metadata_path(repo);
+        let entries = if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read metadata from {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse metadata from {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(&self.entries).context("Failed to serialize metadata")?;
+        std::fs::write(&self.path, contents)
+            .with_context(|| format!("Failed to write metadata to {}", self.path.display()))
+    }
+
+    pub fn get(&self, worktree_path: &Path) -> Option<&WorktreeMetadata> {
+        self.entries.get(&key(worktree_path))
+    }
+
+    pub fn entry(&mut self, worktree_path: &Path) -> &mut WorktreeMetadata {
+        self.entries.entry(key(worktree_path)).or_default()
+    }
+
+    pub fn remove(&mut self, worktree_path: &Path) -> Option<WorktreeMetadata> {
+        self.entries.remove(&key(worktree_path))
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        if let Some(meta) = self.remove(from) {
+            self.entries.insert(key(to), meta);
+        }
+    }
+}
+
+pub fn metadata_path(repo: &GitRepo) -> PathBuf {
+    repo.common_dir.join(METADATA_FILENAME)
+}
+
+pub fn now_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn key(worktree_path: &Path) -> String {
+    // Removed or moved worktrees no longer exist, so fall back to resolving the parent
+    let resolved = worktree_path.canonicalize().ok().or_else(|| {
+        let parent = worktree_path.parent()?.canonicalize().ok()?;
+        Some(parent.join(worktree_path.file_name()?))
+    });
+
+    resolved
+        .unwrap_or_else(|| worktree_path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_entry_and_rename() {
+        let mut store = MetadataStore::default();
+        let from = Path::new("/nonexistent/workty/a");
+        let to = Path::new("/nonexistent/workty/b");
+
+        store.entry(from).note = Some("WIP".to_string());
+        assert_eq!(store.get(from).unwrap().note.as_deref(), Some("WIP"));
+
+        store.rename(from, to);
+        assert!(store.get(from).is_none());
+        assert_eq!(store.get(to).unwrap().note.as_deref(), Some("WIP"));
+    }
+
+    #[test]
+    fn test_metadata_is_empty() {
+        assert!(WorktreeMetadata::default().is_empty());
+        let meta = WorktreeMetadata {
+            ticket: Some("ABC-1".to_string()),
+            ..Default::default()
+        };
+        assert!(!meta.is_empty());
+    }
+}
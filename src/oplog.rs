@@ -0,0 +1,152 @@
+use crate::git::GitRepo;
+use crate::metadata::{now_timestamp, WorktreeMetadata};
+use crate::worktree::Worktree;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const OPLOG_FILENAME: &str = "workty-oplog.json";
+/// Older operations are dropped so the log doesn't grow forever.
+const MAX_OPERATIONS: usize = 50;
+
+/// A worktree as it was just before `rm` or `clean` removed it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemovedWorktree {
+    pub name: String,
+    pub branch: Option<String>,
+    /// Branch tip (or detached HEAD) at removal time
+    pub head: String,
+    pub path: PathBuf,
+    /// Upstream as (remote, merge ref)
+    pub upstream: Option<(String, String)>,
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    pub metadata: Option<WorktreeMetadata>,
+}
+
+impl RemovedWorktree {
+    pub fn capture(repo: &GitRepo, wt: &Worktree, metadata: Option<&WorktreeMetadata>) -> Self {
+        Self {
+            name: wt.name().to_string(),
+            branch: wt.branch_short.clone(),
+            head: wt.head.clone(),
+            path: wt.path.clone(),
+            upstream: wt
+                .branch_short
+                .as_deref()
+                .and_then(|b| repo.branch_upstream_config(b)),
+            locked: wt.locked,
+            lock_reason: wt.lock_reason.clone(),
+            metadata: metadata.cloned(),
+        }
+    }
+}
+
+/// One `rm` or `clean` invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub command: String,
+    pub timestamp: i64,
+    pub removed: Vec<RemovedWorktree>,
+}
+
+/// Removals recorded so `undo` can bring them back, stored under the common git dir.
+#[derive(Debug, Default)]
+pub struct OpLog {
+    path: PathBuf,
+    operations: Vec<Operation>,
+}
+
+impl OpLog {
+    pub fn load(repo: &GitRepo) -> Result<Self> {
+        let path = repo.common_dir.join(OPLOG_FILENAME);
+        let operations = if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read operation log from {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse operation log from {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { path, operations })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let contents = serde_json::to_string_pretty(&self.operations)
+            .context("Failed to serialize operation log")?;
+        std::fs::write(&self.path, contents)
+            .with_context(|| format!("Failed to write operation log to {}", self.path.display()))
+    }
+
+    /// Records removals made by `command`; does nothing if nothing was removed.
+    pub fn record(&mut self, command: &str, removed: Vec<RemovedWorktree>) {
+        if removed.is_empty() {
+            return;
+        }
+
+        self.operations.push(Operation {
+            command: command.to_string(),
+            timestamp: now_timestamp(),
+            removed,
+        });
+
+        if self.operations.len() > MAX_OPERATIONS {
+            let excess = self.operations.len() - MAX_OPERATIONS;
+            self.operations.drain(..excess);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Operation> {
+        self.operations.pop()
+    }
+
+    pub fn push(&mut self, operation: Operation) {
+        self.operations.push(operation);
+    }
+}
+
+/// Records `removed` under `command`, warning instead of failing since the removal
+/// itself already happened.
+pub fn record_removals(repo: &GitRepo, command: &str, removed: Vec<RemovedWorktree>) {
+    let result = OpLog::load(repo).and_then(|mut log| {
+        log.record(command, removed);
+        log.save()
+    });
+    if let Err(e) = result {
+        crate::ui::print_warning(&format!("Failed to record removal for undo: {:#}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn removed(name: &str) -> RemovedWorktree {
+        RemovedWorktree {
+            name: name.to_string(),
+            branch: Some(name.to_string()),
+            head: "0000000000000000000000000000000000000000".to_string(),
+            path: PathBuf::from(format!("/tmp/{}", name)),
+            upstream: None,
+            locked: false,
+            lock_reason: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_record_skips_empty_and_caps_length() {
+        let mut log = OpLog::default();
+        log.record("clean", Vec::new());
+        assert!(log.pop().is_none());
+
+        for i in 0..MAX_OPERATIONS + 5 {
+            log.record("rm", vec![removed(&format!("wt-{}", i))]);
+        }
+        assert_eq!(log.operations.len(), MAX_OPERATIONS);
+
+        let last = log.pop().unwrap();
+        assert_eq!(last.removed[0].name, format!("wt-{}", MAX_OPERATIONS + 4));
+    }
+}
@@ -24,6 +24,15 @@ wcd() {
     fi
 }
 
+# wui - open the dashboard and cd to the chosen worktree
+wui() {
+    local dir
+    dir="$(git workty ui)"
+    if [ -n "$dir" ] && [ -d "$dir" ]; then
+        cd "$dir" || return 1
+    fi
+}
+
 # wnew - create new worktree and cd into it
 wnew() {
     if [ -z "$1" ]; then
@@ -81,6 +90,13 @@ git() {
                     command git "$@"
                 fi
                 ;;
+            ui)
+                local dir
+                dir="$(command git workty ui "${@:3}")"
+                if [ -n "$dir" ] && [ -d "$dir" ]; then
+                    cd "$dir"
+                fi
+                ;;
             new)
                 local dir
                 dir="$(command git workty new "${@:3}" --print-path)"
@@ -122,6 +138,15 @@ wcd() {
     fi
 }
 
+# wui - open the dashboard and cd to the chosen worktree
+wui() {
+    local dir
+    dir="$(git workty ui)"
+    if [[ -n "$dir" ]] && [[ -d "$dir" ]]; then
+        cd "$dir"
+    fi
+}
+
 # wnew - create new worktree and cd into it
 wnew() {
     if [[ -z "$1" ]]; then
@@ -179,6 +204,13 @@ git() {
                     command git "$@"
                 fi
                 ;;
+            ui)
+                local dir
+                dir="$(command git workty ui "${@:3}")"
+                if [[ -n "$dir" ]] && [[ -d "$dir" ]]; then
+                    cd "$dir"
+                fi
+                ;;
             new)
                 local dir
                 dir="$(command git workty new "${@:3}" --print-path)"
@@ -219,6 +251,14 @@ function wcd
     end
 end
 
+# wui - open the dashboard and cd to the chosen worktree
+function wui
+    set -l dir (git workty ui)
+    if test -n "$dir" -a -d "$dir"
+        cd "$dir"
+    end
+end
+
 # wnew - create new worktree and cd into it
 function wnew
     if test (count $argv) -eq 0
@@ -270,6 +310,11 @@ function git --wraps git
                 else
                     command git $argv
                 end
+            case ui
+                set -l dir (command git workty ui $argv[3..])
+                if test -n "$dir" -a -d "$dir"
+                    cd "$dir"
+                end
             case new
                 set -l dir (command git workty new $argv[3..] --print-path)
                 if test -n "$dir" -a -d "$dir"
@@ -307,6 +352,14 @@ function wcd {
     }
 }
 
+# wui - open the dashboard and cd to the chosen worktree
+function wui {
+    $dir = git workty ui
+    if ($dir -and (Test-Path $dir)) {
+        Set-Location $dir
+    }
+}
+
 # wnew - create new worktree and cd into it
 function wnew {
     param([Parameter(Mandatory=$true)][string]$Name)
@@ -334,7 +387,7 @@ function wgo {
     if wrap_git {
         output.push_str(
             r#"# Note: Git wrapper for PowerShell requires more complex setup.
-# Consider using the wcd, wnew, and wgo functions directly.
+# Consider using the wcd, wui, wnew, and wgo functions directly.
 
 "#,
         );
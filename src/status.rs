@@ -1,25 +1,190 @@
+use crate::config::Config;
 use crate::git::GitRepo;
+use crate::metadata::MetadataStore;
 use crate::worktree::Worktree;
 use anyhow::Result;
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WorktreeStatus {
+    /// Number of changed paths; one path can be both staged and modified
     pub dirty_count: usize,
+    /// `dirty_count` broken down by kind of change
+    #[serde(default)]
+    pub changes: ChangeCounts,
+    /// Stashes made on this worktree's branch
+    #[serde(default)]
+    pub stash_count: usize,
+    /// Rebase, merge, etc. stopped partway through
+    pub operation: Option<Operation>,
     pub upstream: Option<String>,
     pub ahead: Option<usize>,
     pub behind: Option<usize>,
     /// Seconds since last commit (HEAD)
     pub last_commit_time: Option<i64>,
-    /// Behind count relative to origin/main or origin/master
+    /// Behind count relative to the base branch
     pub behind_main: Option<usize>,
+    /// Base branch `behind_main` was measured against (e.g. `upstream/develop`)
+    pub base: Option<String>,
     /// Number of commits with no upstream tracking (unpushed branch)
     pub untracked_commits: Option<usize>,
     /// True if upstream branch has been deleted on remote
     pub upstream_gone: bool,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeCounts {
+    pub staged: usize,
+    /// Unstaged edits, deletions and type changes to tracked files
+    pub modified: usize,
+    /// New files; an untracked directory counts once
+    pub untracked: usize,
+    pub conflicted: usize,
+}
+
+/// A git operation in progress, from the worktree's `git2::RepositoryState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Merge,
+    Revert,
+    CherryPick,
+    Bisect,
+    Rebase,
+    ApplyMailbox,
+}
+
+impl Operation {
+    pub fn from_state(state: git2::RepositoryState) -> Option<Self> {
+        use git2::RepositoryState as S;
+
+        match state {
+            S::Clean => None,
+            S::Merge => Some(Self::Merge),
+            S::Revert | S::RevertSequence => Some(Self::Revert),
+            S::CherryPick | S::CherryPickSequence => Some(Self::CherryPick),
+            S::Bisect => Some(Self::Bisect),
+            S::Rebase | S::RebaseInteractive | S::RebaseMerge | S::ApplyMailboxOrRebase => {
+                Some(Self::Rebase)
+            }
+            S::ApplyMailbox => Some(Self::ApplyMailbox),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Merge => "merging",
+            Self::Revert => "reverting",
+            Self::CherryPick => "cherry-picking",
+            Self::Bisect => "bisecting",
+            Self::Rebase => "rebasing",
+            Self::ApplyMailbox => "applying patches",
+        }
+    }
+
+    /// The command that backs out of the operation.
+    pub fn abort_command(self) -> &'static str {
+        match self {
+            Self::Merge => "git merge --abort",
+            Self::Revert => "git revert --abort",
+            Self::CherryPick => "git cherry-pick --abort",
+            Self::Bisect => "git bisect reset",
+            Self::Rebase => "git rebase --abort",
+            Self::ApplyMailbox => "git am --abort",
+        }
+    }
+}
+
+/// A resolved base branch that worktrees are compared against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseRef {
+    /// Remote the branch lives on, or `None` for a local branch
+    pub remote: Option<String>,
+    pub branch: String,
+    pub oid: git2::Oid,
+}
+
+impl BaseRef {
+    /// Resolves `base` to a remote-tracking branch, preferring `<remote>/<base>`
+    /// and falling back to the local branch. `base` may also name a remote-tracking
+    /// branch directly (e.g. `upstream/develop`).
+    pub fn resolve(repo: &GitRepo, remote: &str, base: &str) -> Option<Self> {
+        let repo_lock = repo.repo.lock().ok()?;
+        let target = |name: &str| {
+            repo_lock
+                .find_reference(name)
+                .ok()
+                .and_then(|r| r.resolve().ok())
+                .and_then(|r| r.target())
+        };
+
+        if let Some((base_remote, branch)) = base.split_once('/') {
+            if let Some(oid) = target(&format!("refs/remotes/{}", base)) {
+                return Some(Self {
+                    remote: Some(base_remote.to_string()),
+                    branch: branch.to_string(),
+                    oid,
+                });
+            }
+        }
+
+        if let Some(oid) = target(&format!("refs/remotes/{}/{}", remote, base)) {
+            return Some(Self {
+                remote: Some(remote.to_string()),
+                branch: base.to_string(),
+                oid,
+            });
+        }
+
+        target(&format!("refs/heads/{}", base)).map(|oid| Self {
+            remote: None,
+            branch: base.to_string(),
+            oid,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        match &self.remote {
+            Some(remote) => format!("{}/{}", remote, self.branch),
+            None => self.branch.clone(),
+        }
+    }
+}
+
+/// The configured base plus per-worktree overrides recorded in metadata.
+#[derive(Debug, Default)]
+pub struct BaseRefs {
+    default: Option<BaseRef>,
+    overrides: HashMap<PathBuf, BaseRef>,
+}
+
+impl BaseRefs {
+    pub fn load(repo: &GitRepo, config: &Config, worktrees: &[Worktree]) -> Self {
+        let default = BaseRef::resolve(repo, &config.remote, &config.base);
+        let mut overrides = HashMap::new();
+
+        // Metadata is best-effort here; an unreadable store just means no overrides
+        if let Ok(store) = MetadataStore::load(repo) {
+            for wt in worktrees {
+                let base = store.get(&wt.path).and_then(|meta| meta.base.as_deref());
+                if let Some(base_ref) = base.and_then(|b| BaseRef::resolve(repo, &config.remote, b))
+                {
+                    overrides.insert(wt.path.clone(), base_ref);
+                }
+            }
+        }
+
+        Self { default, overrides }
+    }
+
+    pub fn get(&self, worktree_path: &Path) -> Option<&BaseRef> {
+        self.overrides.get(worktree_path).or(self.default.as_ref())
+    }
+}
+
 impl WorktreeStatus {
     pub fn is_dirty(&self) -> bool {
         self.dirty_count > 0
@@ -59,38 +224,24 @@ impl WorktreeStatus {
     }
 }
 
-pub fn get_worktree_status(repo: &GitRepo, worktree: &Worktree) -> WorktreeStatus {
-    // Open the worktree repo once and reuse it for all status queries
-    let wt_repo = match git2::Repository::open(&worktree.path) {
-        Ok(r) => r,
-        Err(_) => {
-            return WorktreeStatus::default();
-        }
-    };
-
-    let dirty_count = get_dirty_count(&wt_repo);
-    let (upstream, ahead, behind, upstream_gone) = get_ahead_behind(&wt_repo, worktree);
-    let last_commit_time = get_last_commit_time(&wt_repo);
-    let behind_main = get_behind_main(&wt_repo, repo);
-    let untracked_commits = if upstream.is_none() && !worktree.detached {
-        get_untracked_commit_count(&wt_repo, repo)
-    } else {
-        None
-    };
+pub fn get_worktree_status(worktree: &Worktree, base: Option<&BaseRef>) -> WorktreeStatus {
+    get_worktree_status_full(worktree, base)
+}
 
-    WorktreeStatus {
-        dirty_count,
-        upstream,
-        ahead,
-        behind,
-        last_commit_time,
-        behind_main,
-        untracked_commits,
-        upstream_gone,
+/// Recomputes just the uncommitted-change counts of an existing status, for
+/// statuses that may have missed edits to files.
+pub fn refresh_changes(worktree: &Worktree, status: &mut WorktreeStatus) {
+    if let Ok(repo) = git2::Repository::open(&worktree.path) {
+        (status.dirty_count, status.changes) = get_changes(&repo);
     }
 }
 
 fn get_dirty_count(repo: &git2::Repository) -> usize {
+    get_changes(repo).0
+}
+
+/// Returns the number of changed paths and what kind of changes they have.
+fn get_changes(repo: &git2::Repository) -> (usize, ChangeCounts) {
     // Use git2's status API with optimizations for speed
     let mut opts = git2::StatusOptions::new();
     opts.include_untracked(true)
@@ -98,10 +249,64 @@ fn get_dirty_count(repo: &git2::Repository) -> usize {
         .exclude_submodules(true)
         .no_refresh(true); // Don't refresh index from disk
 
-    match repo.statuses(Some(&mut opts)) {
-        Ok(statuses) => statuses.len(),
-        Err(_) => 0,
+    let statuses = match repo.statuses(Some(&mut opts)) {
+        Ok(statuses) => statuses,
+        Err(_) => return (0, ChangeCounts::default()),
+    };
+
+    let mut counts = ChangeCounts::default();
+    for entry in statuses.iter() {
+        let s = entry.status();
+        if s.is_conflicted() {
+            // Conflicts also show up as index and worktree changes; count them once
+            counts.conflicted += 1;
+            continue;
+        }
+        if s.intersects(
+            git2::Status::INDEX_NEW
+                | git2::Status::INDEX_MODIFIED
+                | git2::Status::INDEX_DELETED
+                | git2::Status::INDEX_RENAMED
+                | git2::Status::INDEX_TYPECHANGE,
+        ) {
+            counts.staged += 1;
+        }
+        if s.intersects(
+            git2::Status::WT_MODIFIED
+                | git2::Status::WT_DELETED
+                | git2::Status::WT_RENAMED
+                | git2::Status::WT_TYPECHANGE,
+        ) {
+            counts.modified += 1;
+        }
+        if s.is_wt_new() {
+            counts.untracked += 1;
+        }
     }
+
+    (statuses.len(), counts)
+}
+
+/// Counts stashes made on the worktree's branch. Stashes are shared by every
+/// worktree, so they're told apart by the `WIP on <branch>:` message git writes.
+fn get_stash_count(repo: &git2::Repository, worktree: &Worktree) -> usize {
+    let Some(branch) = worktree.branch_short.as_deref() else {
+        return 0;
+    };
+    let Ok(reflog) = repo.reflog("refs/stash") else {
+        return 0;
+    };
+
+    let wip = format!("WIP on {}:", branch);
+    let named = format!("On {}:", branch);
+    reflog
+        .iter()
+        .filter(|entry| {
+            entry
+                .message()
+                .is_some_and(|m| m.starts_with(&wip) || m.starts_with(&named))
+        })
+        .count()
 }
 
 fn get_ahead_behind(
@@ -171,57 +376,14 @@ fn get_last_commit_time(repo: &git2::Repository) -> Option<i64> {
     Some(now - time.seconds())
 }
 
-fn get_behind_main(wt_repo: &git2::Repository, main_repo: &GitRepo) -> Option<usize> {
-    // Get HEAD of this worktree
-    let head = wt_repo.head().ok()?;
-    let head_oid = head.target()?;
-
-    // Find origin/main or origin/master in the main repo
-    let main_repo_lock = main_repo.repo.lock().ok()?;
-
-    let main_oid = main_repo_lock
-        .find_reference("refs/remotes/origin/main")
-        .or_else(|_| main_repo_lock.find_reference("refs/remotes/origin/master"))
-        .ok()?
-        .target()?;
-
-    // Calculate how far behind main this branch is
-    // We need to use the worktree repo for the graph calculation
-    // but the OIDs should be the same across repos sharing the same object store
-    match wt_repo.graph_ahead_behind(head_oid, main_oid) {
-        Ok((_ahead, behind)) => Some(behind),
-        Err(_) => None,
-    }
-}
-
-fn get_untracked_commit_count(wt_repo: &git2::Repository, main_repo: &GitRepo) -> Option<usize> {
-    // Count commits on this branch that aren't on origin/main or origin/master
-    // This is for branches with no upstream set
-    let head = wt_repo.head().ok()?;
-    let head_oid = head.target()?;
-
-    let main_repo_lock = main_repo.repo.lock().ok()?;
-
-    let main_oid = main_repo_lock
-        .find_reference("refs/remotes/origin/main")
-        .or_else(|_| main_repo_lock.find_reference("refs/remotes/origin/master"))
-        .ok()?
-        .target()?;
-
-    match wt_repo.graph_ahead_behind(head_oid, main_oid) {
-        Ok((ahead, _behind)) => Some(ahead),
-        Err(_) => None,
-    }
-}
-
-pub fn get_all_statuses(repo: &GitRepo, worktrees: &[Worktree]) -> Vec<(Worktree, WorktreeStatus)> {
-    // Pre-compute main branch OID once for all worktrees
-    let main_oid = get_main_branch_oid(repo);
-
+pub fn get_all_statuses(
+    worktrees: &[Worktree],
+    bases: &BaseRefs,
+) -> Vec<(Worktree, WorktreeStatus)> {
     worktrees
         .par_iter()
         .map(|worktree| {
-            let status = get_worktree_status_full(worktree, main_oid);
+            let status = get_worktree_status_full(worktree, bases.get(&worktree.path));
             (worktree.clone(), status)
         })
         .collect()
@@ -229,30 +391,19 @@ pub fn get_all_statuses(repo: &GitRepo, worktrees: &[Worktree]) -> Vec<(Worktree
 
 /// Fast version that skips the expensive dirty file check
 pub fn get_all_statuses_fast(
-    repo: &GitRepo,
     worktrees: &[Worktree],
+    bases: &BaseRefs,
 ) -> Vec<(Worktree, WorktreeStatus)> {
-    let main_oid = get_main_branch_oid(repo);
-
     worktrees
         .par_iter()
         .map(|worktree| {
-            let status = get_worktree_status_minimal(worktree, main_oid);
+            let status = get_worktree_status_minimal(worktree, bases.get(&worktree.path));
             (worktree.clone(), status)
         })
         .collect()
 }
 
-fn get_main_branch_oid(repo: &GitRepo) -> Option<git2::Oid> {
-    let repo_lock = repo.repo.lock().ok()?;
-    let reference = repo_lock
-        .find_reference("refs/remotes/origin/main")
-        .or_else(|_| repo_lock.find_reference("refs/remotes/origin/master"))
-        .ok()?;
-    reference.target()
-}
-
-fn get_worktree_status_full(worktree: &Worktree, main_oid: Option<git2::Oid>) -> WorktreeStatus {
+fn get_worktree_status_full(worktree: &Worktree, base: Option<&BaseRef>) -> WorktreeStatus {
     // Open the worktree repo once and reuse it for all status queries
     let wt_repo = match git2::Repository::open(&worktree.path) {
         Ok(r) => r,
@@ -261,46 +412,31 @@ fn get_worktree_status_full(worktree: &Worktree, main_oid: Option<git2::Oid>) ->
         }
     };
 
-    let dirty_count = get_dirty_count(&wt_repo);
+    let (dirty_count, changes) = get_changes(&wt_repo);
     let (upstream, ahead, behind, upstream_gone) = get_ahead_behind(&wt_repo, worktree);
     let last_commit_time = get_last_commit_time(&wt_repo);
 
-    // Use pre-computed main_oid for faster calculation
-    let (behind_main, untracked_commits) = if let Some(main_oid) = main_oid {
-        let head_oid = wt_repo.head().ok().and_then(|h| h.target());
-        if let Some(head_oid) = head_oid {
-            let (ahead_of_main, behind_of_main) = wt_repo
-                .graph_ahead_behind(head_oid, main_oid)
-                .unwrap_or((0, 0));
-
-            let untracked = if upstream.is_none() && !worktree.detached {
-                Some(ahead_of_main)
-            } else {
-                None
-            };
-
-            (Some(behind_of_main), untracked)
-        } else {
-            (None, None)
-        }
-    } else {
-        (None, None)
-    };
+    let (behind_main, untracked_commits) =
+        compare_with_base(&wt_repo, worktree, upstream.is_none(), base);
 
     WorktreeStatus {
         dirty_count,
+        changes,
+        stash_count: get_stash_count(&wt_repo, worktree),
+        operation: Operation::from_state(wt_repo.state()),
         upstream,
         ahead,
         behind,
         last_commit_time,
         behind_main,
+        base: base.map(BaseRef::name),
         untracked_commits,
         upstream_gone,
     }
 }
 
 /// Minimal status - skips expensive dirty check for fast dashboard
-fn get_worktree_status_minimal(worktree: &Worktree, main_oid: Option<git2::Oid>) -> WorktreeStatus {
+fn get_worktree_status_minimal(worktree: &Worktree, base: Option<&BaseRef>) -> WorktreeStatus {
     let wt_repo = match git2::Repository::open(&worktree.path) {
         Ok(r) => r,
         Err(_) => {
@@ -312,39 +448,55 @@ fn get_worktree_status_minimal(worktree: &Worktree, main_oid: Option<git2::Oid>)
     let (upstream, ahead, behind, upstream_gone) = get_ahead_behind(&wt_repo, worktree);
     let last_commit_time = get_last_commit_time(&wt_repo);
 
-    let (behind_main, untracked_commits) = if let Some(main_oid) = main_oid {
-        let head_oid = wt_repo.head().ok().and_then(|h| h.target());
-        if let Some(head_oid) = head_oid {
-            let (ahead_of_main, behind_of_main) = wt_repo
-                .graph_ahead_behind(head_oid, main_oid)
-                .unwrap_or((0, 0));
-
-            let untracked = if upstream.is_none() && !worktree.detached {
-                Some(ahead_of_main)
-            } else {
-                None
-            };
-
-            (Some(behind_of_main), untracked)
-        } else {
-            (None, None)
-        }
-    } else {
-        (None, None)
-    };
+    let (behind_main, untracked_commits) =
+        compare_with_base(&wt_repo, worktree, upstream.is_none(), base);
 
     WorktreeStatus {
         dirty_count: 0, // Skip dirty check in fast mode
+        changes: ChangeCounts::default(),
+        stash_count: get_stash_count(&wt_repo, worktree),
+        operation: Operation::from_state(wt_repo.state()),
         upstream,
         ahead,
         behind,
         last_commit_time,
         behind_main,
+        base: base.map(BaseRef::name),
         untracked_commits,
         upstream_gone,
     }
 }
 
+/// Returns (behind base, commits not on base) for HEAD.
+/// The latter is only reported for branches without an upstream.
+fn compare_with_base(
+    wt_repo: &git2::Repository,
+    worktree: &Worktree,
+    no_upstream: bool,
+    base: Option<&BaseRef>,
+) -> (Option<usize>, Option<usize>) {
+    let base = match base {
+        Some(b) => b,
+        None => return (None, None),
+    };
+    let head_oid = match wt_repo.head().ok().and_then(|h| h.target()) {
+        Some(oid) => oid,
+        None => return (None, None),
+    };
+
+    let (ahead_of_base, behind_base) = wt_repo
+        .graph_ahead_behind(head_oid, base.oid)
+        .unwrap_or((0, 0));
+
+    let untracked = if no_upstream && !worktree.detached {
+        Some(ahead_of_base)
+    } else {
+        None
+    };
+
+    (Some(behind_base), untracked)
+}
+
 pub fn is_worktree_dirty(worktree: &Worktree) -> bool {
     match git2::Repository::open(&worktree.path) {
         Ok(repo) => get_dirty_count(&repo) > 0,
@@ -352,7 +504,13 @@ pub fn is_worktree_dirty(worktree: &Worktree) -> bool {
     }
 }
 
+/// The operation the worktree is in the middle of, if any.
+pub fn worktree_operation(worktree: &Worktree) -> Option<Operation> {
+    let repo = git2::Repository::open(&worktree.path).ok()?;
+    Operation::from_state(repo.state())
+}
+
 #[allow(dead_code)]
-pub fn check_branch_merged(repo: &GitRepo, branch: &str, base: &str) -> Result<bool> {
-    repo.is_merged(branch, base)
+pub fn check_branch_merged(repo: &GitRepo, branch: &str, base: &str, remote: &str) -> Result<bool> {
+    repo.is_merged(branch, base, remote)
 }
@@ -0,0 +1,331 @@
+use crate::git::GitRepo;
+use crate::metadata::now_timestamp;
+use crate::status::{get_worktree_status, BaseRef, BaseRefs, Operation, WorktreeStatus};
+use crate::worktree::Worktree;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const STATUS_CACHE_FILENAME: &str = "workty-status-cache.json";
+/// Entries older than this are still served, but refreshed in the background, since
+/// edits to tracked files don't show up in the fingerprint.
+const REVALIDATE_AFTER_SECS: i64 = 30;
+
+/// What a cached status depends on. Any difference means the entry is recomputed
+/// before it is shown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    head: Option<String>,
+    /// Modification time of the worktree's index, in milliseconds
+    index_mtime: Option<u128>,
+    upstream: Option<String>,
+    base: Option<String>,
+    /// Tip of `refs/stash`, which moves on every stash push and pop
+    stash: Option<String>,
+    /// Rebase, merge, etc. in progress, which can change without touching HEAD
+    operation: Option<Operation>,
+}
+
+impl Fingerprint {
+    pub fn compute(worktree: &Worktree, base: Option<&BaseRef>) -> Option<Self> {
+        let repo = git2::Repository::open(&worktree.path).ok()?;
+        let head = repo.head().ok();
+
+        let index_mtime = std::fs::metadata(repo.path().join("index"))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis());
+
+        let upstream = head
+            .as_ref()
+            .filter(|h| h.is_branch())
+            .and_then(|h| h.shorthand())
+            .and_then(|name| repo.find_branch(name, git2::BranchType::Local).ok())
+            .and_then(|branch| branch.upstream().ok())
+            .and_then(|upstream| upstream.get().target())
+            .map(|oid| oid.to_string());
+
+        Some(Self {
+            head: head.and_then(|h| h.target()).map(|oid| oid.to_string()),
+            index_mtime,
+            upstream,
+            base: base.map(|b| b.oid.to_string()),
+            stash: repo
+                .refname_to_id("refs/stash")
+                .ok()
+                .map(|oid| oid.to_string()),
+            operation: Operation::from_state(repo.state()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    status: WorktreeStatus,
+    computed_at: i64,
+}
+
+/// Worktree statuses from earlier runs, keyed by worktree path and stored under the
+/// common git dir.
+#[derive(Debug, Default)]
+pub struct StatusCache {
+    path: PathBuf,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl StatusCache {
+    /// Loads the cache, starting empty if it is missing or unreadable.
+    pub fn load(repo: &GitRepo) -> Self {
+        let path = repo.common_dir.join(STATUS_CACHE_FILENAME);
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self { path, entries }
+    }
+
+    /// Writes through a temporary file so concurrent readers never see half a cache.
+    pub fn save(&self) -> Result<()> {
+        let contents =
+            serde_json::to_string(&self.entries).context("Failed to serialize status cache")?;
+        let tmp = self
+            .path
+            .with_extension(format!("tmp.{}", std::process::id()));
+        std::fs::write(&tmp, contents)
+            .with_context(|| format!("Failed to write status cache to {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write status cache to {}", self.path.display()))
+    }
+
+    /// Returns the cached status if its fingerprint still matches, and whether it is
+    /// old enough to need revalidating.
+    fn get(
+        &self,
+        worktree: &Worktree,
+        fingerprint: &Fingerprint,
+    ) -> Option<(WorktreeStatus, bool)> {
+        let entry = self.entries.get(&key(&worktree.path))?;
+        if &entry.fingerprint != fingerprint {
+            return None;
+        }
+
+        let age = now_timestamp() - entry.computed_at;
+        let mut status = entry.status.clone();
+        // Stored relative to when it was computed
+        status.last_commit_time = status.last_commit_time.map(|t| t + age.max(0));
+        Some((status, age > REVALIDATE_AFTER_SECS))
+    }
+
+    fn insert(&mut self, worktree: &Worktree, fingerprint: Fingerprint, status: WorktreeStatus) {
+        self.entries.insert(
+            key(&worktree.path),
+            CacheEntry {
+                fingerprint,
+                status,
+                computed_at: now_timestamp(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, worktree_path: &Path) {
+        self.entries.remove(&key(worktree_path));
+    }
+
+    /// Drops entries for worktrees that no longer exist.
+    fn retain(&mut self, worktrees: &[Worktree]) {
+        let live: Vec<String> = worktrees.iter().map(|wt| key(&wt.path)).collect();
+        self.entries.retain(|k, _| live.contains(k));
+    }
+}
+
+/// Computes every status from scratch and stores the results for `--cached` readers.
+pub fn refresh_statuses(
+    repo: &GitRepo,
+    worktrees: &[Worktree],
+    bases: &BaseRefs,
+) -> Vec<(Worktree, WorktreeStatus)> {
+    let computed: Vec<(Worktree, WorktreeStatus, Option<Fingerprint>)> = worktrees
+        .par_iter()
+        .map(|wt| {
+            let base = bases.get(&wt.path);
+            let fingerprint = Fingerprint::compute(wt, base);
+            (wt.clone(), get_worktree_status(wt, base), fingerprint)
+        })
+        .collect();
+
+    let mut cache = StatusCache::load(repo);
+    cache.retain(worktrees);
+    for (wt, status, fingerprint) in &computed {
+        if let Some(fingerprint) = fingerprint {
+            cache.insert(wt, fingerprint.clone(), status.clone());
+        }
+    }
+    // The cache is only an optimization, so failing to write it isn't an error
+    let _ = cache.save();
+
+    computed
+        .into_iter()
+        .map(|(wt, status, _)| (wt, status))
+        .collect()
+}
+
+/// Serves statuses from the cache where the fingerprint still matches and computes
+/// the rest. Returns true if any served entry is due for revalidation.
+pub fn cached_statuses(
+    repo: &GitRepo,
+    worktrees: &[Worktree],
+    bases: &BaseRefs,
+) -> (Vec<(Worktree, WorktreeStatus)>, bool) {
+    let cache = StatusCache::load(repo);
+
+    let results: Vec<(Worktree, WorktreeStatus, Option<Fingerprint>, bool)> = worktrees
+        .par_iter()
+        .map(|wt| {
+            let base = bases.get(&wt.path);
+            let fingerprint = Fingerprint::compute(wt, base);
+            match fingerprint.as_ref().and_then(|f| cache.get(wt, f)) {
+                Some((status, stale)) => (wt.clone(), status, None, stale),
+                None => (
+                    wt.clone(),
+                    get_worktree_status(wt, base),
+                    fingerprint,
+                    false,
+                ),
+            }
+        })
+        .collect();
+
+    let computed: Vec<_> = results
+        .iter()
+        .filter_map(|(wt, status, fingerprint, _)| Some((wt, status, fingerprint.clone()?)))
+        .collect();
+    if !computed.is_empty() {
+        let mut cache = cache;
+        cache.retain(worktrees);
+        for (wt, status, fingerprint) in computed {
+            cache.insert(wt, fingerprint, status.clone());
+        }
+        let _ = cache.save();
+    }
+
+    let stale = results.iter().any(|(_, _, _, stale)| *stale);
+    let statuses = results
+        .into_iter()
+        .map(|(wt, status, _, _)| (wt, status))
+        .collect();
+    (statuses, stale)
+}
+
+/// Refreshes the cache in a detached `git workty` process, so the caller can return
+/// right away with what it already had.
+pub fn revalidate_in_background(repo: &GitRepo) {
+    if let Ok(exe) = std::env::current_exe() {
+        let _ = Command::new(exe)
+            .arg("-C")
+            .arg(&repo.root)
+            .args(["list", "--refresh-cache"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+/// Forgets cached statuses for worktrees workty just changed. Best-effort: a stale
+/// entry is caught by its fingerprint or the next revalidation anyway.
+pub fn invalidate(repo: &GitRepo, worktree_paths: &[&Path]) {
+    let mut cache = StatusCache::load(repo);
+    for path in worktree_paths {
+        cache.remove(path);
+    }
+    let _ = cache.save();
+}
+
+fn key(worktree_path: &Path) -> String {
+    worktree_path
+        .canonicalize()
+        .unwrap_or_else(|_| worktree_path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worktree(path: &str) -> Worktree {
+        Worktree {
+            path: PathBuf::from(path),
+            head: String::new(),
+            branch: None,
+            branch_short: None,
+            detached: false,
+            locked: false,
+            lock_reason: None,
+            prunable: false,
+        }
+    }
+
+    fn fingerprint(head: &str) -> Fingerprint {
+        Fingerprint {
+            head: Some(head.to_string()),
+            index_mtime: Some(1),
+            upstream: None,
+            base: None,
+            stash: None,
+            operation: None,
+        }
+    }
+
+    #[test]
+    fn test_get_checks_fingerprint_and_age() {
+        let wt = worktree("/nonexistent/workty/a");
+        let mut cache = StatusCache::default();
+        let status = WorktreeStatus {
+            dirty_count: 3,
+            last_commit_time: Some(100),
+            ..Default::default()
+        };
+        cache.insert(&wt, fingerprint("abc"), status);
+
+        let (cached, stale) = cache.get(&wt, &fingerprint("abc")).unwrap();
+        assert_eq!(cached.dirty_count, 3);
+        assert!(!stale);
+        assert!(cache.get(&wt, &fingerprint("def")).is_none());
+
+        // A new stash or an operation starting invalidates the entry too
+        let stashed = Fingerprint {
+            stash: Some("123".to_string()),
+            ..fingerprint("abc")
+        };
+        assert!(cache.get(&wt, &stashed).is_none());
+        let rebasing = Fingerprint {
+            operation: Some(Operation::Rebase),
+            ..fingerprint("abc")
+        };
+        assert!(cache.get(&wt, &rebasing).is_none());
+
+        cache.entries.values_mut().next().unwrap().computed_at -= REVALIDATE_AFTER_SECS + 10;
+        let (cached, stale) = cache.get(&wt, &fingerprint("abc")).unwrap();
+        assert!(stale);
+        assert!(cached.last_commit_time.unwrap() >= 100 + REVALIDATE_AFTER_SECS + 10);
+
+        cache.retain(&[]);
+        assert!(cache.get(&wt, &fingerprint("abc")).is_none());
+    }
+
+    #[test]
+    fn test_statuses_written_before_change_counts_still_load() {
+        let status: WorktreeStatus =
+            serde_json::from_str(r#"{"dirty_count":2,"upstream_gone":false}"#).unwrap();
+        assert_eq!(status.dirty_count, 2);
+        assert_eq!(status.stash_count, 0);
+        assert_eq!(status.changes.staged, 0);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::git::GitRepo;
-use crate::status::WorktreeStatus;
+use crate::metadata::{MetadataStore, WorktreeMetadata};
+use crate::status::{ChangeCounts, Operation, WorktreeStatus};
 use crate::worktree::Worktree;
 use owo_colors::OwoColorize;
 use serde::Serialize;
@@ -30,6 +31,8 @@ pub struct Icons {
     pub arrow_up: &'static str,
     pub arrow_down: &'static str,
     pub rebase: &'static str,
+    pub locked: &'static str,
+    pub stash: &'static str,
 }
 
 impl Icons {
@@ -41,6 +44,8 @@ impl Icons {
             arrow_up: "↑",
             arrow_down: "↓",
             rebase: "⟳",
+            locked: "🔒",
+            stash: "≡",
         }
     }
 
@@ -52,6 +57,8 @@ impl Icons {
             arrow_up: "^",
             arrow_down: "v",
             rebase: "R",
+            locked: "L",
+            stash: "$",
         }
     }
 
@@ -68,10 +75,11 @@ pub fn print_worktree_list(
     repo: &GitRepo,
     worktrees: &[(Worktree, WorktreeStatus)],
     current_path: &Path,
+    metadata: &MetadataStore,
     opts: &UiOptions,
 ) {
     if opts.json {
-        print_worktree_list_json(repo, worktrees, current_path);
+        print_worktree_list_json(repo, worktrees, current_path, metadata);
         return;
     }
 
@@ -85,28 +93,31 @@ pub fn print_worktree_list(
         .unwrap_or(10)
         .max(6); // minimum width for "BRANCH" header
 
+    let dirty_width = dirty_width(worktrees.iter().map(|(_, status)| status), &icons);
+
     // Print header
     if opts.color {
         println!(
-            "  {:width$}  {:>6}  {:>6}  {:>5}  {:>6}  {}",
+            "  {:width$}  {:>dirty_width$}  {:>6}  {:>5}  {:>6}  {}",
             "BRANCH".dimmed(),
             "DIRTY".dimmed(),
             "SYNC".dimmed(),
             "AGE".dimmed(),
             "REBASE".dimmed(),
             "PATH".dimmed(),
-            width = max_name_len
+            width = max_name_len,
+            dirty_width = dirty_width
         );
     } else {
         println!(
-            "  {:width$}  {:>6}  {:>6}  {:>5}  {:>6}  {}",
+            "  {:width$}  {:>dirty_width$}  {:>6}  {:>5}  {:>6}  PATH",
             "BRANCH",
             "DIRTY",
             "SYNC",
             "AGE",
             "REBASE",
-            "PATH",
-            width = max_name_len
+            width = max_name_len,
+            dirty_width = dirty_width
         );
     }
 
@@ -118,11 +129,16 @@ pub fn print_worktree_list(
         let name = wt.name();
         let name_padded = format!("{:width$}", name, width = max_name_len);
 
-        let dirty_str = format_dirty(status, &icons, opts);
+        let dirty_str = format_dirty(status, &icons, opts, dirty_width);
         let sync_str = format_sync(status, &icons);
         let time_str = format_time(status.last_commit_time);
         let rebase_str = format_rebase(status, &icons, opts);
-        let path_str = shorten_path(&wt.path);
+        let path_str = if wt.locked {
+            format!("{} {}", icons.locked, shorten_path(&wt.path))
+        } else {
+            shorten_path(&wt.path)
+        };
+        let note_str = metadata.get(&wt.path).map(format_note).unwrap_or_default();
 
         if opts.color {
             let name_colored = if is_current {
@@ -140,43 +156,89 @@ pub fn print_worktree_list(
             };
 
             println!(
-                "{} {}  {:>6}  {:>6}  {:>5}  {:>6}  {}",
+                "{} {}  {}  {:>6}  {:>5}  {:>6}  {}{}",
                 marker_colored,
                 name_colored,
                 dirty_str,
                 sync_str,
                 time_str.dimmed(),
                 rebase_str,
-                path_str.dimmed()
+                path_str.dimmed(),
+                note_str.cyan()
             );
         } else {
             println!(
-                "{} {}  {:>6}  {:>6}  {:>5}  {:>6}  {}",
-                marker, name_padded, dirty_str, sync_str, time_str, rebase_str, path_str
+                "{} {}  {}  {:>6}  {:>5}  {:>6}  {}{}",
+                marker, name_padded, dirty_str, sync_str, time_str, rebase_str, path_str, note_str
             );
         }
     }
 }
 
-fn format_dirty(status: &WorktreeStatus, icons: &Icons, opts: &UiOptions) -> String {
-    if status.dirty_count > 0 {
-        let s = format!("{} {:>3}", icons.dirty, status.dirty_count);
-        if opts.color {
-            s.yellow().to_string()
-        } else {
-            s
-        }
+/// Right-aligned to `width`, which `dirty_width` computes for a whole column.
+pub fn format_dirty(
+    status: &WorktreeStatus,
+    icons: &Icons,
+    opts: &UiOptions,
+    width: usize,
+) -> String {
+    let s = format!("{:>width$}", dirty_text(status, icons), width = width);
+    if !opts.color {
+        s
+    } else if status.is_dirty() || status.operation.is_some() {
+        s.yellow().to_string()
     } else {
-        let s = format!("{} {:>3}", icons.clean, "-");
-        if opts.color {
-            s.green().to_string()
-        } else {
-            s
-        }
+        s.green().to_string()
+    }
+}
+
+/// Width of the DIRTY column: at least the header's, or the widest breakdown.
+pub fn dirty_width<'a>(
+    statuses: impl IntoIterator<Item = &'a WorktreeStatus>,
+    icons: &Icons,
+) -> usize {
+    statuses
+        .into_iter()
+        .map(|status| dirty_text(status, icons).chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(6)
+}
+
+fn dirty_text(status: &WorktreeStatus, icons: &Icons) -> String {
+    let changes = format_changes(status, icons);
+    match (status.is_dirty(), changes.is_empty()) {
+        (true, false) => format!("{} {}", icons.dirty, changes),
+        (true, true) => format!("{} {:>3}", icons.dirty, status.dirty_count),
+        (false, false) => format!("{} {}", icons.clean, changes),
+        (false, true) => format!("{} {:>3}", icons.clean, "-"),
+    }
+}
+
+/// `+` staged, `~` modified, `?` untracked and `!` conflicted counts, then stashes
+/// and any operation in progress, e.g. `+1 ~2 ≡1 rebasing`. Empty when there's
+/// nothing to report.
+pub fn format_changes(status: &WorktreeStatus, icons: &Icons) -> String {
+    let changes = &status.changes;
+    let mut parts: Vec<String> = [
+        ("+", changes.staged),
+        ("~", changes.modified),
+        ("?", changes.untracked),
+        ("!", changes.conflicted),
+        (icons.stash, status.stash_count),
+    ]
+    .into_iter()
+    .filter(|(_, n)| *n > 0)
+    .map(|(symbol, n)| format!("{}{}", symbol, n))
+    .collect();
+
+    if let Some(operation) = status.operation {
+        parts.push(operation.label().to_string());
     }
+    parts.join(" ")
 }
 
-fn format_sync(status: &WorktreeStatus, icons: &Icons) -> String {
+pub fn format_sync(status: &WorktreeStatus, icons: &Icons) -> String {
     match (status.ahead, status.behind) {
         (Some(a), Some(b)) => {
             format!("{}{} {}{}", icons.arrow_up, a, icons.arrow_down, b)
@@ -185,6 +247,15 @@ fn format_sync(status: &WorktreeStatus, icons: &Icons) -> String {
     }
 }
 
+fn format_note(meta: &WorktreeMetadata) -> String {
+    match (&meta.ticket, &meta.note) {
+        (Some(ticket), Some(note)) => format!("  [{}] {}", ticket, note),
+        (Some(ticket), None) => format!("  [{}]", ticket),
+        (None, Some(note)) => format!("  {}", note),
+        (None, None) => String::new(),
+    }
+}
+
 pub fn format_time(seconds: Option<i64>) -> String {
     match seconds {
         Some(s) if s < 60 => "now".to_string(),
@@ -197,7 +268,7 @@ pub fn format_time(seconds: Option<i64>) -> String {
     }
 }
 
-fn format_rebase(status: &WorktreeStatus, icons: &Icons, opts: &UiOptions) -> String {
+pub fn format_rebase(status: &WorktreeStatus, icons: &Icons, opts: &UiOptions) -> String {
     if let Some(n) = status.behind_main {
         if n > 0 {
             let s = format!("{} {:>3}", icons.rebase, n);
@@ -223,6 +294,46 @@ pub fn shorten_path(path: &Path) -> String {
     path.display().to_string()
 }
 
+/// Bumped when a field is removed or changes meaning; adding fields keeps the version.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct JsonEnvelope<'a, T: Serialize> {
+    schema_version: u32,
+    command: &'a str,
+    #[serde(flatten)]
+    result: &'a T,
+}
+
+/// Prints a command's `--json` result on stdout, tagged with the schema version and
+/// command name so consumers can check what they are reading.
+pub fn print_json<T: Serialize>(command: &str, result: &T) {
+    let envelope = JsonEnvelope {
+        schema_version: JSON_SCHEMA_VERSION,
+        command,
+        result,
+    };
+    let json = serde_json::to_string_pretty(&envelope).unwrap_or_else(|_| "{}".to_string());
+    println!("{}", json);
+}
+
+/// A worktree removed by `rm` or `clean`.
+#[derive(Serialize)]
+pub struct JsonRemoved {
+    pub name: String,
+    pub path: String,
+    pub branch: Option<String>,
+    /// Archive directory, if the worktree was archived first
+    pub archive: Option<String>,
+}
+
+/// A worktree an operation left alone, and why.
+#[derive(Serialize)]
+pub struct JsonSkipped {
+    pub name: String,
+    pub reason: String,
+}
+
 #[derive(Serialize)]
 struct JsonOutput {
     repo: RepoInfo,
@@ -244,18 +355,25 @@ struct JsonWorktree {
     head: String,
     detached: bool,
     locked: bool,
+    lock_reason: Option<String>,
     dirty_count: usize,
+    changes: ChangeCounts,
+    stash_count: usize,
+    operation: Option<Operation>,
     upstream: Option<String>,
     ahead: Option<usize>,
     behind: Option<usize>,
     last_commit_seconds: Option<i64>,
     behind_main: Option<usize>,
+    base: Option<String>,
+    metadata: Option<WorktreeMetadata>,
 }
 
 fn print_worktree_list_json(
     repo: &GitRepo,
     worktrees: &[(Worktree, WorktreeStatus)],
     current_path: &Path,
+    metadata: &MetadataStore,
 ) {
     let json_worktrees: Vec<JsonWorktree> = worktrees
         .iter()
@@ -266,12 +384,18 @@ fn print_worktree_list_json(
             head: wt.head.clone(),
             detached: wt.detached,
             locked: wt.locked,
+            lock_reason: wt.lock_reason.clone(),
             dirty_count: status.dirty_count,
+            changes: status.changes,
+            stash_count: status.stash_count,
+            operation: status.operation,
             upstream: status.upstream.clone(),
             ahead: status.ahead,
             behind: status.behind,
             last_commit_seconds: status.last_commit_time,
             behind_main: status.behind_main,
+            base: status.base.clone(),
+            metadata: metadata.get(&wt.path).cloned(),
         })
         .collect();
 
@@ -284,8 +408,7 @@ fn print_worktree_list_json(
         worktrees: json_worktrees,
     };
 
-    let json = serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string());
-    println!("{}", json);
+    print_json("list", &output);
 }
 
 pub fn print_error(msg: &str, hint: Option<&str>) {
@@ -1,5 +1,9 @@
+use crate::error::WorktyError;
 use crate::git::GitRepo;
 use anyhow::{Context, Result};
+use console::Term;
+use dialoguer::FuzzySelect;
+use is_terminal::IsTerminal;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 
@@ -11,6 +15,7 @@ pub struct Worktree {
     pub branch_short: Option<String>,
     pub detached: bool,
     pub locked: bool,
+    pub lock_reason: Option<String>,
     pub prunable: bool,
 }
 
@@ -31,7 +36,7 @@ impl Worktree {
     }
 }
 
-fn check_same_path(p1: &Path, p2: &Path) -> bool {
+pub fn check_same_path(p1: &Path, p2: &Path) -> bool {
     match (p1.canonicalize(), p2.canonicalize()) {
         (Ok(c1), Ok(c2)) => c1 == c2,
         _ => false, // If either fails to canonicalize, they're not the same
@@ -53,7 +58,12 @@ pub fn list_worktrees(repo: &GitRepo) -> Result<Vec<Worktree>> {
         let path = wt.path().to_path_buf();
 
         let should_prune = wt.is_prunable(None).unwrap_or(false);
-        let is_locked = matches!(wt.is_locked(), Ok(git2::WorktreeLockStatus::Locked(_)));
+        let (is_locked, lock_reason) = match wt.is_locked() {
+            Ok(git2::WorktreeLockStatus::Locked(reason)) => {
+                (true, reason.filter(|r| !r.is_empty()))
+            }
+            _ => (false, None),
+        };
 
         // If prunable, we might not be able to open it
         if should_prune {
@@ -64,6 +74,7 @@ pub fn list_worktrees(repo: &GitRepo) -> Result<Vec<Worktree>> {
                 branch_short: None,
                 detached: false,
                 locked: is_locked,
+                lock_reason,
                 prunable: true,
             });
             continue;
@@ -81,6 +92,7 @@ pub fn list_worktrees(repo: &GitRepo) -> Result<Vec<Worktree>> {
                     branch_short,
                     detached,
                     locked: is_locked,
+                    lock_reason,
                     prunable: false,
                 });
             }
@@ -93,6 +105,7 @@ pub fn list_worktrees(repo: &GitRepo) -> Result<Vec<Worktree>> {
                     branch_short: None,
                     detached: false,
                     locked: is_locked,
+                    lock_reason,
                     prunable: true, // Treat as broken
                 });
             }
@@ -131,6 +144,7 @@ pub fn list_worktrees(repo: &GitRepo) -> Result<Vec<Worktree>> {
                 branch_short,
                 detached,
                 locked: false,
+                lock_reason: None,
                 prunable: false,
             });
         }
@@ -158,11 +172,159 @@ fn get_repo_head_info(repo: &git2::Repository) -> (String, Option<String>, Optio
     }
 }
 
-pub fn find_worktree<'a>(worktrees: &'a [Worktree], name: &str) -> Option<&'a Worktree> {
-    worktrees.iter().find(|worktree| {
-        worktree.branch_short.as_deref() == Some(name)
-            || worktree.path.file_name().and_then(|s| s.to_str()) == Some(name)
-    })
+/// Result of looking a worktree up by name.
+#[derive(Debug)]
+pub enum Lookup<'a> {
+    Found(&'a Worktree),
+    /// Several worktrees matched equally well
+    Ambiguous(Vec<&'a Worktree>),
+    NotFound,
+}
+
+/// Finds a worktree by, in order: exact branch or directory name, path, PR number
+/// (`#123`), unique suffix after a `/` (`login` for `feat/login`), unique prefix, and
+/// finally a fuzzy subsequence match. The first tier with any match decides.
+pub fn find_worktree<'a>(worktrees: &'a [Worktree], name: &str) -> Lookup<'a> {
+    find_worktree_with(worktrees, name, true)
+}
+
+/// Like [`find_worktree`], but stops after the `/` suffix tier, so a typo can't
+/// land on some other worktree through a prefix or fuzzy match.
+pub fn find_worktree_exact<'a>(worktrees: &'a [Worktree], name: &str) -> Lookup<'a> {
+    find_worktree_with(worktrees, name, false)
+}
+
+fn find_worktree_with<'a>(worktrees: &'a [Worktree], name: &str, loose: bool) -> Lookup<'a> {
+    let dir_name = |wt: &Worktree| {
+        wt.path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(str::to_string)
+    };
+
+    if let Some(wt) = worktrees
+        .iter()
+        .find(|wt| wt.branch_short.as_deref() == Some(name))
+        .or_else(|| {
+            worktrees
+                .iter()
+                .find(|wt| dir_name(wt).as_deref() == Some(name))
+        })
+    {
+        return Lookup::Found(wt);
+    }
+
+    if let Some(wt) = find_by_path(worktrees, name) {
+        return Lookup::Found(wt);
+    }
+
+    if let Some(number) = name.strip_prefix('#') {
+        let pr_dir = format!("pr-{}", number);
+        let pr_sibling = format!(".{}", pr_dir);
+        return tier(worktrees, |wt| {
+            dir_name(wt).is_some_and(|d| d == pr_dir || d.ends_with(&pr_sibling))
+                || wt.branch_short.as_deref() == Some(&pr_dir)
+        });
+    }
+
+    let lower = name.to_lowercase();
+    let names = |wt: &Worktree| -> Vec<String> {
+        wt.branch_short
+            .iter()
+            .cloned()
+            .chain(dir_name(wt))
+            .map(|n| n.to_lowercase())
+            .collect()
+    };
+
+    let suffix = format!("/{}", lower);
+    let tiers: [&dyn Fn(&str) -> bool; 3] = [
+        &|n| n.ends_with(&suffix),
+        &|n| n.starts_with(&lower),
+        &|n| is_subsequence(&lower, n),
+    ];
+    let tiers = if loose { &tiers[..] } else { &tiers[..1] };
+    for matches in tiers {
+        match tier(worktrees, |wt| names(wt).iter().any(|n| matches(n))) {
+            Lookup::NotFound => continue,
+            found => return found,
+        }
+    }
+
+    Lookup::NotFound
+}
+
+/// Like [`find_worktree`], but turns a miss into an error. An ambiguous name opens a
+/// picker over the candidates when there's a terminal, and otherwise lists them.
+pub fn resolve_worktree<'a>(worktrees: &'a [Worktree], name: &str) -> Result<&'a Worktree> {
+    resolve_lookup(find_worktree(worktrees, name), name)
+}
+
+/// [`resolve_worktree`] for commands that change or remove the worktree, using
+/// [`find_worktree_exact`].
+pub fn resolve_worktree_exact<'a>(worktrees: &'a [Worktree], name: &str) -> Result<&'a Worktree> {
+    resolve_lookup(find_worktree_exact(worktrees, name), name)
+}
+
+fn resolve_lookup<'a>(lookup: Lookup<'a>, name: &str) -> Result<&'a Worktree> {
+    match lookup {
+        Lookup::Found(wt) => Ok(wt),
+        Lookup::NotFound => Err(WorktyError::WorktreeNotFound(name.to_string()).into()),
+        Lookup::Ambiguous(candidates) => {
+            if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+                return Err(WorktyError::Ambiguous {
+                    name: name.to_string(),
+                    candidates: candidates.iter().map(|wt| wt.name().to_string()).collect(),
+                }
+                .into());
+            }
+
+            let items: Vec<&str> = candidates.iter().map(|wt| wt.name()).collect();
+            let selection = FuzzySelect::new()
+                .with_prompt(format!("'{}' matches several worktrees", name))
+                .items(&items)
+                .default(0)
+                .interact_on_opt(&Term::stderr())?;
+            match selection {
+                Some(idx) => Ok(candidates[idx]),
+                None => Err(WorktyError::Aborted.into()),
+            }
+        }
+    }
+}
+
+fn tier<'a>(worktrees: &'a [Worktree], matches: impl Fn(&Worktree) -> bool) -> Lookup<'a> {
+    let mut found: Vec<&Worktree> = worktrees.iter().filter(|wt| matches(wt)).collect();
+    match found.len() {
+        0 => Lookup::NotFound,
+        1 => Lookup::Found(found.remove(0)),
+        _ => Lookup::Ambiguous(found),
+    }
+}
+
+/// Only names that look like paths are tried, so a directory in the current
+/// directory can't shadow a branch name.
+fn find_by_path<'a>(worktrees: &'a [Worktree], name: &str) -> Option<&'a Worktree> {
+    let path = Path::new(name);
+    let looks_like_path =
+        name.contains(std::path::MAIN_SEPARATOR) || name.starts_with('.') || path.is_absolute();
+    if !looks_like_path {
+        return None;
+    }
+    let path = path.canonicalize().ok()?;
+
+    // The deepest worktree containing the path, for nested layouts
+    worktrees
+        .iter()
+        .filter_map(|wt| Some((wt, wt.path.canonicalize().ok()?)))
+        .filter(|(_, wt_path)| path.starts_with(wt_path))
+        .max_by_key(|(_, wt_path)| wt_path.components().count())
+        .map(|(wt, _)| wt)
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|c| chars.any(|h| h == c))
 }
 
 pub fn slug_from_branch(branch: &str) -> String {
@@ -193,4 +355,86 @@ mod tests {
             "feature-add-user-auth"
         );
     }
+
+    fn wt(branch: &str, dir: &str) -> Worktree {
+        Worktree {
+            path: PathBuf::from("/nonexistent/workty").join(dir),
+            head: String::new(),
+            branch: Some(format!("refs/heads/{}", branch)),
+            branch_short: Some(branch.to_string()),
+            detached: false,
+            locked: false,
+            lock_reason: None,
+            prunable: false,
+        }
+    }
+
+    fn found<'a>(lookup: Lookup<'a>) -> Option<&'a str> {
+        match lookup {
+            Lookup::Found(wt) => wt.branch_short.as_deref(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_find_worktree_tiers() {
+        let worktrees = vec![
+            wt("main", "repo"),
+            wt("feat/login", "feat-login"),
+            wt("fix/login-redirect", "fix-login-redirect"),
+            wt("feature-x", "pr-42"),
+            wt("alice/feat/billing", "alice-feat-billing"),
+        ];
+
+        // Exact branch and directory names
+        assert_eq!(found(find_worktree(&worktrees, "main")), Some("main"));
+        assert_eq!(
+            found(find_worktree(&worktrees, "feat-login")),
+            Some("feat/login")
+        );
+
+        // Suffix after a slash wins over the longer prefix match
+        assert_eq!(
+            found(find_worktree(&worktrees, "login")),
+            Some("feat/login")
+        );
+        assert_eq!(
+            found(find_worktree(&worktrees, "feat/billing")),
+            Some("alice/feat/billing")
+        );
+
+        assert_eq!(found(find_worktree(&worktrees, "#42")), Some("feature-x"));
+        assert_eq!(
+            found(find_worktree(&worktrees, "ALICE")),
+            Some("alice/feat/billing")
+        );
+        assert_eq!(
+            found(find_worktree(&worktrees, "fxlr")),
+            Some("fix/login-redirect")
+        );
+
+        match find_worktree(&worktrees, "f") {
+            Lookup::Ambiguous(candidates) => assert!(candidates.len() > 1),
+            other => panic!("expected ambiguity, got {:?}", other),
+        }
+        assert!(matches!(find_worktree(&worktrees, "zzz"), Lookup::NotFound));
+
+        // Exact lookups keep the suffix tier but not prefix or fuzzy matches
+        assert_eq!(
+            found(find_worktree_exact(&worktrees, "login")),
+            Some("feat/login")
+        );
+        assert_eq!(
+            found(find_worktree_exact(&worktrees, "#42")),
+            Some("feature-x")
+        );
+        assert!(matches!(
+            find_worktree_exact(&worktrees, "ALICE"),
+            Lookup::NotFound
+        ));
+        assert!(matches!(
+            find_worktree_exact(&worktrees, "fxlr"),
+            Lookup::NotFound
+        ));
+    }
 }
@@ -181,6 +181,8 @@ fn test_rm_refuses_dirty_without_force() {
         "rm should fail for dirty worktree without --force"
     );
 
+    assert_eq!(rm_output.status.code(), Some(4));
+
     let stderr = String::from_utf8_lossy(&rm_output.stderr);
     assert!(
         stderr.contains("uncommitted") || stderr.contains("--force"),
@@ -309,3 +311,1321 @@ fn test_new_subcommand_help() {
         "new help should show --print-path flag"
     );
 }
+
+#[test]
+fn test_post_create_hook_runs_in_worktree() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    git(repo_dir, &["branch", "develop"]);
+
+    // A nested `git workty` must not read the hook's WORKTY_BASE as an override
+    std::fs::write(
+        repo_dir.join(".git/workty.toml"),
+        format!(
+            "[hooks]\npost_create = [\"echo \\\"$WORKTY_BRANCH $WORKTY_BASE $('{}' config get base)\\\" > hook.txt\"]\n",
+            env!("CARGO_BIN_EXE_git-workty")
+        ),
+    )
+    .unwrap();
+
+    let output = workty_success(
+        repo_dir,
+        &[
+            "new",
+            "feat/hooked",
+            "--from",
+            "develop",
+            "--no-fetch",
+            "--no-push",
+            "--print-path",
+        ],
+    );
+    let wt_path = std::path::Path::new(output.trim());
+
+    let contents = std::fs::read_to_string(wt_path.join("hook.txt")).unwrap();
+    assert_eq!(contents.trim(), "feat/hooked develop main");
+}
+
+#[test]
+fn test_failing_hook_aborts() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    workty_success(repo_dir, &["new", "keep-me", "--print-path"]);
+
+    std::fs::write(
+        repo_dir.join(".git/workty.toml"),
+        "[hooks]\npre_remove = [\"exit 3\"]\n",
+    )
+    .unwrap();
+
+    let rm_output = workty(repo_dir, &["rm", "keep-me", "--yes"]);
+    assert!(
+        !rm_output.status.success(),
+        "rm should fail when pre_remove hook fails"
+    );
+
+    let list_output = workty_success(repo_dir, &["list", "--no-color"]);
+    assert!(
+        list_output.contains("keep-me"),
+        "Worktree should still exist: {}",
+        list_output
+    );
+}
+
+#[test]
+fn test_new_copies_configured_files() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    std::fs::write(repo_dir.join(".env"), "SECRET=1\n").unwrap();
+    std::fs::write(repo_dir.join(".git/workty.toml"), "copy = [\".env\"]\n").unwrap();
+
+    let output = workty_success(repo_dir, &["new", "with-env", "--print-path"]);
+    let wt_path = std::path::Path::new(output.trim());
+    assert_eq!(
+        std::fs::read_to_string(wt_path.join(".env")).unwrap(),
+        "SECRET=1\n"
+    );
+
+    let output = workty_success(
+        repo_dir,
+        &["new", "without-env", "--no-copy", "--print-path"],
+    );
+    let wt_path = std::path::Path::new(output.trim());
+    assert!(
+        !wt_path.join(".env").exists(),
+        "--no-copy should skip copying"
+    );
+}
+
+#[test]
+fn test_note_shows_in_list() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    workty_success(
+        repo_dir,
+        &["new", "noted", "--ticket", "ABC-1", "--print-path"],
+    );
+    workty_success(repo_dir, &["note", "noted", "waiting on review"]);
+
+    let list_output = workty_success(repo_dir, &["list", "--no-color"]);
+    assert!(
+        list_output.contains("[ABC-1] waiting on review"),
+        "List should show note: {}",
+        list_output
+    );
+
+    let json = workty_success(repo_dir, &["list", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let wt = parsed["worktrees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["branch_short"].as_str() == Some("noted"))
+        .expect("Should find noted worktree");
+    assert_eq!(wt["metadata"]["ticket"].as_str(), Some("ABC-1"));
+    assert_eq!(wt["metadata"]["note"].as_str(), Some("waiting on review"));
+    assert!(wt["metadata"]["created_at"].as_i64().is_some());
+}
+
+#[test]
+fn test_mv_renames_branch_and_directory() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let old_path = workty_success(repo_dir, &["new", "feat/old-name", "--print-path"]);
+    workty_success(repo_dir, &["mv", "feat/old-name", "feat/new-name"]);
+
+    let new_path = workty_success(repo_dir, &["go", "feat/new-name"]);
+    let new_path = std::path::Path::new(new_path.trim());
+
+    assert!(new_path.exists(), "Moved worktree should exist");
+    assert!(
+        new_path.ends_with("feat-new-name"),
+        "Directory should follow the new branch name: {}",
+        new_path.display()
+    );
+    assert!(!std::path::Path::new(old_path.trim()).exists());
+
+    let branches = git(repo_dir, &["branch", "--list", "feat/old-name"]);
+    assert!(branches.trim().is_empty(), "Old branch should be gone");
+}
+
+#[test]
+fn test_locked_worktree_requires_double_force() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    workty_success(repo_dir, &["new", "locked-wt", "--print-path"]);
+    workty_success(repo_dir, &["lock", "locked-wt", "--reason", "keep"]);
+
+    let list_json = workty_success(repo_dir, &["list", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&list_json).unwrap();
+    let wt = parsed["worktrees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["branch_short"].as_str() == Some("locked-wt"))
+        .expect("Should find locked worktree");
+    assert_eq!(wt["locked"].as_bool(), Some(true));
+    assert_eq!(wt["lock_reason"].as_str(), Some("keep"));
+
+    let rm_output = workty(repo_dir, &["rm", "locked-wt", "--force", "--yes"]);
+    assert!(
+        !rm_output.status.success(),
+        "rm --force should refuse a locked worktree"
+    );
+
+    workty_success(repo_dir, &["rm", "locked-wt", "-f", "-f", "--yes"]);
+
+    let list_output = workty_success(repo_dir, &["list", "--no-color"]);
+    assert!(
+        !list_output.contains("locked-wt"),
+        "Worktree should be removed"
+    );
+}
+
+#[test]
+fn test_config_layers_merge_per_key() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    std::fs::write(
+        repo_dir.join("workty.toml"),
+        "base = \"develop\"\nopen_cmd = \"code\"\n",
+    )
+    .unwrap();
+    std::fs::write(repo_dir.join(".git/workty.toml"), "base = \"trunk\"\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_git-workty"))
+        .current_dir(repo_dir)
+        .args(["config", "--show-origin"])
+        .env("WORKTY_LAYOUT", "nested")
+        .env("WORKTY_ARCHIVE", "true")
+        .env("WORKTY_BRANCH_TEMPLATE", "{type}/{slug}")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains(".git/workty.toml\tbase = \"trunk\""),
+        "base should come from .git/workty.toml: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("workty.toml\topen_cmd = \"code\""),
+        "open_cmd should survive from the repo file: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("env:WORKTY_LAYOUT\tlayout = \"nested\""),
+        "layout should come from the environment: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("env:WORKTY_ARCHIVE\tarchive = true"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("env:WORKTY_BRANCH_TEMPLATE\tbranch_template = \"{type}/{slug}\""),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_config_set_get_unset() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    workty_success(repo_dir, &["config", "set", "base", "develop"]);
+    let value = workty_success(repo_dir, &["config", "get", "base"]);
+    assert_eq!(value.trim(), "develop");
+
+    let invalid = workty(repo_dir, &["config", "set", "hooks.on_failure", "explode"]);
+    assert!(
+        !invalid.status.success(),
+        "Invalid values should be rejected"
+    );
+
+    workty_success(repo_dir, &["config", "unset", "base"]);
+    let contents = std::fs::read_to_string(repo_dir.join(".git/workty.toml")).unwrap();
+    assert!(!contents.contains("develop"), "base should be removed");
+}
+
+#[test]
+fn test_sibling_and_nested_layouts() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path().join("myrepo");
+    std::fs::create_dir(&repo_dir).unwrap();
+
+    git_init_repo(&repo_dir);
+
+    std::fs::write(repo_dir.join(".git/workty.toml"), "layout = \"sibling\"\n").unwrap();
+    let output = workty_success(&repo_dir, &["new", "feat/side", "--print-path"]);
+    let sibling = std::path::Path::new(output.trim());
+    assert_eq!(
+        sibling.canonicalize().unwrap(),
+        temp.path().join("myrepo.feat-side").canonicalize().unwrap()
+    );
+
+    let nested_root = temp.path().join("trees");
+    std::fs::write(
+        repo_dir.join(".git/workty.toml"),
+        format!(
+            "layout = \"nested\"\nroot = \"{}\"\n",
+            nested_root.display()
+        ),
+    )
+    .unwrap();
+    let output = workty_success(&repo_dir, &["new", "feat/deep", "--print-path"]);
+    assert_eq!(
+        std::path::Path::new(output.trim()),
+        nested_root.join("feat").join("deep")
+    );
+
+    // The sibling worktree no longer matches the nested layout
+    let doctor = workty(&repo_dir, &["doctor", "--yes"]);
+    assert!(doctor.status.success());
+    assert!(nested_root.join("feat").join("side").exists());
+    assert!(!sibling.exists());
+}
+
+#[test]
+fn test_doctor_leaves_pr_worktrees_in_place() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path().join("myrepo");
+    std::fs::create_dir(&repo_dir).unwrap();
+
+    git_init_repo(&repo_dir);
+    std::fs::write(repo_dir.join(".git/workty.toml"), "layout = \"sibling\"\n").unwrap();
+
+    // Like `pr`: a directory named after the PR, switched to the PR's branch
+    let output = workty_success(
+        &repo_dir,
+        &["new", "pr-7", "--no-fetch", "--no-push", "--print-path"],
+    );
+    let pr_path = std::path::PathBuf::from(output.trim());
+    git(&pr_path, &["checkout", "-q", "-b", "contributor/fix"]);
+
+    let doctor = workty(&repo_dir, &["doctor", "--yes"]);
+    assert!(doctor.status.success());
+    assert!(pr_path.exists(), "doctor moved the PR worktree");
+    assert!(!temp.path().join("myrepo.contributor-fix").exists());
+}
+
+#[test]
+fn test_new_branch_template_and_pattern() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    std::fs::write(
+        repo_dir.join(".git/workty.toml"),
+        "branch_template = \"{user}/{type}/{ticket}-{slug}\"\nbranch_pattern = \"[a-z-]+/(feat|fix)/[A-Z]+-[0-9]+-[a-z0-9-]+\"\n",
+    )
+    .unwrap();
+
+    workty_success(
+        repo_dir,
+        &[
+            "new",
+            "login",
+            "--type",
+            "feat",
+            "--ticket",
+            "ABC-1",
+            "--print-path",
+        ],
+    );
+    let branches = git(
+        repo_dir,
+        &["branch", "--list", "test-user/feat/ABC-1-login"],
+    );
+    assert!(!branches.trim().is_empty(), "Templated branch should exist");
+
+    let bad = workty(
+        repo_dir,
+        &["new", "login", "--type", "chore", "--ticket", "ABC-2"],
+    );
+    assert!(!bad.status.success(), "Pattern mismatch should be rejected");
+    let stderr = String::from_utf8_lossy(&bad.stderr);
+    assert!(
+        stderr.contains("branch_pattern"),
+        "Error should mention the pattern: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_ui_requires_tty() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let output = workty(repo_dir, &["ui"]);
+    assert!(
+        !output.status.success(),
+        "ui should refuse to run without a TTY"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("non-TTY"));
+}
+
+#[test]
+fn test_status_uses_configured_base_and_remote() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path().join("repo");
+    std::fs::create_dir_all(&repo_dir).unwrap();
+
+    git_init_repo(&repo_dir);
+
+    // Simulate `upstream/develop` being one commit ahead of main
+    let ahead = git(
+        &repo_dir,
+        &[
+            "commit-tree",
+            "HEAD^{tree}",
+            "-p",
+            "HEAD",
+            "-m",
+            "upstream work",
+        ],
+    );
+    git(
+        &repo_dir,
+        &["update-ref", "refs/remotes/upstream/develop", ahead.trim()],
+    );
+
+    let wt_path = temp.path().join("feat");
+    git(
+        &repo_dir,
+        &[
+            "worktree",
+            "add",
+            "-b",
+            "feat",
+            wt_path.to_str().unwrap(),
+            "main",
+        ],
+    );
+
+    workty_success(&repo_dir, &["config", "set", "base", "develop"]);
+    workty_success(&repo_dir, &["config", "set", "remote", "upstream"]);
+
+    let find_feat = |json: &str| -> serde_json::Value {
+        let parsed: serde_json::Value = serde_json::from_str(json).unwrap();
+        parsed["worktrees"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|wt| wt["branch_short"].as_str() == Some("feat"))
+            .cloned()
+            .expect("Should find feat worktree")
+    };
+
+    let feat = find_feat(&workty_success(&repo_dir, &["list", "--json"]));
+    assert_eq!(feat["base"].as_str(), Some("upstream/develop"));
+    assert_eq!(feat["behind_main"].as_u64(), Some(1));
+
+    // A per-worktree base recorded in metadata wins over the config
+    workty_success(&repo_dir, &["note", "feat", "--base", "main"]);
+
+    let feat = find_feat(&workty_success(&repo_dir, &["list", "--json"]));
+    assert_eq!(feat["base"].as_str(), Some("main"));
+    assert_eq!(feat["behind_main"].as_u64(), Some(0));
+
+    // Clearing drops the override, so the configured base applies again
+    workty_success(&repo_dir, &["note", "feat", "--clear"]);
+
+    let feat = find_feat(&workty_success(&repo_dir, &["list", "--json"]));
+    assert_eq!(feat["base"].as_str(), Some("upstream/develop"));
+    assert_eq!(feat["behind_main"].as_u64(), Some(1));
+}
+
+#[test]
+fn test_creation_base_does_not_override_configured_base() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let ahead = git(
+        repo_dir,
+        &[
+            "commit-tree",
+            "HEAD^{tree}",
+            "-p",
+            "HEAD",
+            "-m",
+            "develop work",
+        ],
+    );
+    git(
+        repo_dir,
+        &["update-ref", "refs/heads/develop", ahead.trim()],
+    );
+
+    workty_success(
+        repo_dir,
+        &["new", "feat", "--from", "main", "--no-fetch", "--no-push"],
+    );
+    workty_success(repo_dir, &["config", "set", "base", "develop"]);
+
+    let json = workty_success(repo_dir, &["list", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let feat = parsed["worktrees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["branch_short"].as_str() == Some("feat"))
+        .cloned()
+        .expect("Should find feat worktree");
+    assert_eq!(feat["base"].as_str(), Some("develop"));
+    assert_eq!(feat["behind_main"].as_u64(), Some(1));
+
+    let note = workty_success(repo_dir, &["note", "feat"]);
+    assert!(note.contains("created from: main"), "{}", note);
+    assert!(!note.contains("base:"), "{}", note);
+}
+
+#[test]
+fn test_base_follows_remote_default_branch() {
+    use std::time::{Duration, Instant};
+
+    let temp = TempDir::new().unwrap();
+    let upstream = temp.path().join("upstream");
+    std::fs::create_dir_all(&upstream).unwrap();
+
+    git_init_repo(&upstream);
+    git(&upstream, &["checkout", "-b", "develop"]);
+
+    let clone = temp.path().join("clone");
+    git(
+        temp.path(),
+        &[
+            "clone",
+            "-q",
+            upstream.to_str().unwrap(),
+            clone.to_str().unwrap(),
+        ],
+    );
+
+    // origin/HEAD is set by the clone
+    let base = workty_success(&clone, &["config", "get", "base"]);
+    assert_eq!(base.trim(), "develop");
+
+    // Without origin/HEAD the remote is asked in the background, and later runs
+    // use its cached answer
+    git(&clone, &["remote", "set-head", "origin", "-d"]);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut base = workty_success(&clone, &["config", "get", "base"]);
+    while base.trim() != "develop" && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        base = workty_success(&clone, &["config", "get", "base"]);
+    }
+    assert_eq!(base.trim(), "develop");
+    assert!(clone.join(".git/workty-remote-head.json").exists());
+
+    workty_success(&clone, &["config", "set", "base", "main"]);
+    let output = workty(&clone, &["doctor"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("differs from origin's default branch 'develop'"),
+        "doctor should flag the mismatched base: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("git remote set-head origin --auto"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_clean_detects_squash_and_rebase_merges() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let commit_file = |dir: &std::path::Path, file: &str, contents: &str| {
+        std::fs::write(dir.join(file), contents).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-m", file]);
+    };
+
+    // Two commits landed on main as a single squash commit
+    let squashed = workty_success(repo_dir, &["new", "squashed", "--print-path"]);
+    let squashed = std::path::Path::new(squashed.trim());
+    commit_file(squashed, "a.txt", "a\n");
+    commit_file(squashed, "b.txt", "b\n");
+    git(repo_dir, &["merge", "--squash", "squashed"]);
+    git(repo_dir, &["commit", "-m", "Squashed work"]);
+
+    // A commit rebased (cherry-picked) onto main
+    let rebased = workty_success(repo_dir, &["new", "rebased", "--print-path"]);
+    let rebased = std::path::Path::new(rebased.trim());
+    commit_file(rebased, "c.txt", "c\n");
+    commit_file(repo_dir, "main.txt", "main\n");
+    git(repo_dir, &["cherry-pick", "rebased"]);
+
+    let unmerged = workty_success(repo_dir, &["new", "unmerged", "--print-path"]);
+    commit_file(std::path::Path::new(unmerged.trim()), "d.txt", "d\n");
+
+    let output = workty_success(repo_dir, &["clean", "--merged", "--dry-run", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let method = |name: &str| {
+        parsed["candidates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"].as_str() == Some(name))
+            .map(|c| c["merge_method"].as_str().unwrap_or_default().to_string())
+    };
+
+    assert_eq!(method("squashed").as_deref(), Some("squash"));
+    assert_eq!(method("rebased").as_deref(), Some("patch-equivalent"));
+    assert_eq!(method("unmerged"), None);
+
+    let text = workty_success(repo_dir, &["clean", "--merged", "--dry-run"]);
+    assert!(text.contains("squashed (merged: squash)"), "{}", text);
+}
+
+#[test]
+fn test_clean_merged_keeps_untouched_worktrees() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    // Still at main's commit: nothing of its own, so nothing merged
+    let fresh = workty_success(repo_dir, &["new", "fresh", "--print-path"]);
+    let fresh = std::path::PathBuf::from(fresh.trim());
+
+    let output = workty_success(repo_dir, &["clean", "--merged", "--dry-run", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert!(
+        !parsed["candidates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|c| c["name"].as_str() == Some("fresh")),
+        "{}",
+        output
+    );
+
+    workty_success(repo_dir, &["--yes", "clean", "--merged"]);
+    assert!(
+        fresh.exists(),
+        "clean --merged removed a brand-new worktree"
+    );
+}
+
+#[test]
+fn test_rm_archive_and_restore() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let wt_path = workty_success(repo_dir, &["new", "archived", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_path.trim());
+
+    std::fs::write(wt_path.join("feature.txt"), "committed\n").unwrap();
+    git(&wt_path, &["add", "feature.txt"]);
+    git(&wt_path, &["commit", "-m", "Unpushed work"]);
+    std::fs::write(wt_path.join("README.md"), "# Changed\n").unwrap();
+    std::fs::write(wt_path.join("scratch.txt"), "untracked\n").unwrap();
+    workty_success(repo_dir, &["note", "archived", "keep this"]);
+
+    workty_success(
+        repo_dir,
+        &["rm", "archived", "--force", "--archive", "--yes"],
+    );
+    git(repo_dir, &["branch", "-D", "archived"]);
+    assert!(!wt_path.exists(), "Worktree should be removed");
+
+    let archives = workty_success(repo_dir, &["restore"]);
+    assert!(archives.contains("commits + changes"), "{}", archives);
+
+    workty_success(repo_dir, &["restore", "archived"]);
+
+    assert_eq!(
+        std::fs::read_to_string(wt_path.join("feature.txt")).unwrap(),
+        "committed\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(wt_path.join("README.md")).unwrap(),
+        "# Changed\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(wt_path.join("scratch.txt")).unwrap(),
+        "untracked\n"
+    );
+
+    let note = workty_success(repo_dir, &["note", "archived"]);
+    assert!(note.contains("keep this"));
+}
+
+#[test]
+fn test_undo_restores_cleaned_worktrees() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let first = workty_success(repo_dir, &["new", "undo-one", "--print-path"]);
+    let second = workty_success(repo_dir, &["new", "undo-two", "--print-path"]);
+    workty_success(repo_dir, &["note", "undo-one", "remember me"]);
+    workty_success(repo_dir, &["lock", "undo-two", "--reason", "pinned"]);
+    // main moves past both branches, so they count as merged
+    git(repo_dir, &["commit", "--allow-empty", "-m", "moved on"]);
+
+    workty_success(repo_dir, &["clean", "--merged", "-f", "-f", "--yes"]);
+    git(repo_dir, &["branch", "-D", "undo-one", "undo-two"]);
+    assert!(!std::path::Path::new(first.trim()).exists());
+    assert!(!std::path::Path::new(second.trim()).exists());
+
+    workty_success(repo_dir, &["undo"]);
+
+    assert!(std::path::Path::new(first.trim()).exists());
+    assert!(std::path::Path::new(second.trim()).exists());
+
+    let list_json = workty_success(repo_dir, &["list", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&list_json).unwrap();
+    let find = |name: &str| {
+        parsed["worktrees"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|wt| wt["branch_short"].as_str() == Some(name))
+            .cloned()
+            .unwrap_or_else(|| panic!("{} should be restored", name))
+    };
+    assert_eq!(
+        find("undo-one")["metadata"]["note"].as_str(),
+        Some("remember me")
+    );
+    assert_eq!(find("undo-two")["lock_reason"].as_str(), Some("pinned"));
+
+    // The log entry is consumed
+    let output = workty(repo_dir, &["undo"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Nothing to undo"));
+}
+
+#[test]
+fn test_worktrees_sharing_a_directory_name() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+
+    git_init_repo(&repo_dir);
+
+    let first = temp.path().join("a").join("checkout");
+    let second = temp.path().join("b").join("checkout");
+    workty_success(
+        &repo_dir,
+        &[
+            "new",
+            "first",
+            "--path",
+            first.to_str().unwrap(),
+            "--no-push",
+        ],
+    );
+    workty_success(
+        &repo_dir,
+        &[
+            "new",
+            "second",
+            "--path",
+            second.to_str().unwrap(),
+            "--no-push",
+        ],
+    );
+
+    let list = git(&repo_dir, &["worktree", "list", "--porcelain"]);
+    assert!(list.contains("refs/heads/first"), "{}", list);
+    assert!(list.contains("refs/heads/second"), "{}", list);
+
+    workty_success(&repo_dir, &["rm", "second", "--yes"]);
+    assert!(!second.exists());
+    assert!(first.exists());
+
+    // Only the removed worktree's administrative files are gone
+    let list = git(&repo_dir, &["worktree", "list", "--porcelain"]);
+    assert!(list.contains("refs/heads/first"), "{}", list);
+    assert!(!list.contains("refs/heads/second"), "{}", list);
+}
+
+#[test]
+fn test_not_found_exit_code_and_json_error() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let output = workty(repo_dir, &["go", "does-not-exist"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("hint"), "{}", stderr);
+
+    let output = workty(repo_dir, &["--json", "go", "does-not-exist"]);
+    assert_eq!(output.status.code(), Some(3));
+    let parsed: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Error should be JSON on stdout");
+    assert_eq!(parsed["error"]["kind"].as_str(), Some("worktree_not_found"));
+    assert_eq!(parsed["error"]["exit_code"].as_i64(), Some(3));
+    assert!(parsed["error"]["hint"].as_str().is_some());
+}
+
+#[test]
+fn test_failed_git_command_exits_with_git_failed() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    workty_success(repo_dir, &["new", "feat", "--no-fetch", "--no-push"]);
+
+    // git refuses the branch name, which should surface as a git failure
+    let output = workty(repo_dir, &["--json", "mv", "feat", "bad..name"]);
+    assert_eq!(output.status.code(), Some(5));
+    let parsed: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Error should be JSON on stdout");
+    assert_eq!(parsed["error"]["kind"].as_str(), Some("git_failed"));
+}
+
+#[test]
+fn test_clean_without_filter_errors_in_json_mode() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let output = workty(repo_dir, &["--json", "clean"]);
+    assert!(!output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Error should be JSON on stdout");
+    assert!(parsed["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("No filter specified"));
+}
+
+#[test]
+fn test_json_output_is_versioned_per_command() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let parse = |stdout: &str| -> serde_json::Value {
+        serde_json::from_str(stdout).unwrap_or_else(|e| panic!("{}: {}", e, stdout))
+    };
+
+    let created = parse(&workty_success(
+        repo_dir,
+        &["--json", "new", "json-wt", "--no-fetch", "--no-push"],
+    ));
+    assert_eq!(created["schema_version"].as_u64(), Some(1));
+    assert_eq!(created["command"].as_str(), Some("new"));
+    assert_eq!(created["branch"].as_str(), Some("json-wt"));
+    assert_eq!(created["created_branch"].as_bool(), Some(true));
+    let path = created["path"].as_str().unwrap().to_string();
+    assert!(std::path::Path::new(&path).exists());
+
+    let go = parse(&workty_success(repo_dir, &["--json", "go", "json-wt"]));
+    assert_eq!(go["command"].as_str(), Some("go"));
+    assert_eq!(go["path"].as_str(), Some(path.as_str()));
+
+    let removed = parse(&workty_success(
+        repo_dir,
+        &["--json", "--yes", "rm", "json-wt"],
+    ));
+    assert_eq!(removed["command"].as_str(), Some("rm"));
+    assert_eq!(removed["removed"][0]["path"].as_str(), Some(path.as_str()));
+
+    let doctor = parse(&workty_success(repo_dir, &["--json", "doctor"]));
+    assert_eq!(doctor["command"].as_str(), Some("doctor"));
+    let checks = doctor["checks"].as_array().unwrap();
+    assert!(checks
+        .iter()
+        .any(|c| c["id"] == "in_repository" && c["status"] == "ok"));
+}
+
+#[test]
+fn test_go_resolves_partial_names() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let login = workty_success(repo_dir, &["new", "feat/login", "--print-path"]);
+    workty_success(repo_dir, &["new", "feat/logout", "--print-path"]);
+
+    let path = workty_success(repo_dir, &["go", "login"]);
+    assert_eq!(path.trim(), login.trim());
+
+    // By path, from anywhere inside the worktree
+    let nested = std::path::Path::new(login.trim()).join("src");
+    std::fs::create_dir_all(&nested).unwrap();
+    let path = workty_success(repo_dir, &["go", nested.to_str().unwrap()]);
+    assert_eq!(path.trim(), login.trim());
+
+    let output = workty(repo_dir, &["go", "feat/log"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("feat/login") && stderr.contains("feat/logout"),
+        "Ambiguity error should list candidates: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_sync_onto_base_reports_conflicts() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+
+    let clean = workty_success(repo_dir, &["new", "sync-clean", "--print-path"]);
+    let conflict = workty_success(repo_dir, &["new", "sync-conflict", "--print-path"]);
+    let clean = std::path::Path::new(clean.trim());
+    let conflict = std::path::Path::new(conflict.trim());
+
+    std::fs::write(clean.join("other.txt"), "other\n").unwrap();
+    git(clean, &["add", "."]);
+    git(clean, &["commit", "-m", "Unrelated change"]);
+
+    std::fs::write(conflict.join("README.md"), "# Branch\n").unwrap();
+    git(conflict, &["commit", "-am", "Branch README"]);
+
+    std::fs::write(repo_dir.join("README.md"), "# Main\n").unwrap();
+    git(repo_dir, &["commit", "-am", "Main README"]);
+
+    // ff-only can't move either branch, since both have their own commits
+    let output = workty_success(
+        repo_dir,
+        &["--json", "sync", "--onto-base", "--strategy", "ff-only"],
+    );
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    for result in parsed["results"].as_array().unwrap() {
+        assert_eq!(result["outcome"].as_str(), Some("failed"), "{}", output);
+    }
+
+    let output = workty_success(repo_dir, &["--json", "sync", "--onto-base", "-j", "2"]);
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let result = |name: &str| {
+        parsed["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["name"].as_str() == Some(name))
+            .cloned()
+            .unwrap_or_else(|| panic!("{} missing from {}", name, output))
+    };
+
+    assert_eq!(result("sync-clean")["outcome"].as_str(), Some("rebased"));
+    assert_eq!(result("sync-clean")["onto"].as_str(), Some("main"));
+    assert_eq!(
+        result("sync-conflict")["outcome"].as_str(),
+        Some("conflicted")
+    );
+    assert_eq!(
+        result("sync-conflict")["conflicts"][0].as_str(),
+        Some("README.md")
+    );
+
+    // The conflicted rebase was aborted, leaving the branch untouched
+    let readme = std::fs::read_to_string(conflict.join("README.md")).unwrap();
+    assert_eq!(readme, "# Branch\n");
+    assert!(git(clean, &["log", "--oneline"]).contains("Main README"));
+}
+
+#[test]
+fn test_list_cached_follows_worktree_changes() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let wt_output = workty_success(repo_dir, &["new", "cache-test", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_output.trim());
+
+    workty_success(repo_dir, &["list", "--json"]);
+    assert!(repo_dir.join(".git/workty-status-cache.json").exists());
+
+    let find = |output: &str| {
+        let parsed: serde_json::Value = serde_json::from_str(output).unwrap();
+        parsed["worktrees"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|wt| wt["branch_short"].as_str() == Some("cache-test"))
+            .cloned()
+    };
+
+    let cached = find(&workty_success(repo_dir, &["list", "--cached", "--json"]))
+        .expect("Should find cache-test worktree");
+    assert_eq!(cached["dirty_count"].as_u64(), Some(0));
+
+    // Staging touches the index, so the cached entry no longer matches
+    std::fs::write(wt_path.join("staged.txt"), "staged").unwrap();
+    git(&wt_path, &["add", "staged.txt"]);
+    let cached = find(&workty_success(repo_dir, &["list", "--cached", "--json"]))
+        .expect("Should find cache-test worktree");
+    assert_eq!(cached["dirty_count"].as_u64(), Some(1));
+
+    workty_success(repo_dir, &["--yes", "rm", "cache-test", "--force"]);
+    assert!(find(&workty_success(repo_dir, &["list", "--cached", "--json"])).is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_watch_serves_fresh_statuses() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::{Duration, Instant};
+
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let wt_output = workty_success(repo_dir, &["new", "watched", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_output.trim());
+
+    let mut daemon = Command::new(env!("CARGO_BIN_EXE_git-workty"))
+        .current_dir(repo_dir)
+        .arg("watch")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to start watcher");
+
+    let socket = repo_dir.join(".git/workty-watch.sock");
+    let watched_dirty_count = || {
+        let mut stream = UnixStream::connect(&socket).ok()?;
+        stream.write_all(b"{\"request\":\"statuses\"}\n").ok()?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).ok()?;
+        let parsed: serde_json::Value = serde_json::from_str(&line).ok()?;
+        parsed["worktrees"]
+            .as_array()?
+            .iter()
+            .find(|wt| wt["path"].as_str() == wt_path.to_str())?["status"]["dirty_count"]
+            .as_u64()
+    };
+    let wait_for = |expected: u64| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if watched_dirty_count() == Some(expected) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        false
+    };
+
+    assert!(wait_for(0), "Watcher should report the clean worktree");
+    let status = workty_success(repo_dir, &["watch", "--status"]);
+    assert!(status.contains("2 worktree(s)"), "{}", status);
+
+    // Staging writes the worktree's index, which the watcher picks up
+    std::fs::write(wt_path.join("staged.txt"), "staged").unwrap();
+    git(&wt_path, &["add", "staged.txt"]);
+    assert!(wait_for(1), "Watcher should notice the staged file");
+
+    // A plain file edit fires no git event; list re-checks statuses that are a
+    // couple of seconds old instead of trusting the daemon
+    std::fs::write(wt_path.join("edited.txt"), "edited").unwrap();
+    std::thread::sleep(Duration::from_millis(2100));
+    let json = workty_success(repo_dir, &["list", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let listed = parsed["worktrees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["path"].as_str() == wt_path.to_str())
+        .cloned()
+        .expect("Should list the watched worktree");
+    assert_eq!(listed["dirty_count"].as_u64(), Some(2), "{}", listed);
+
+    workty_success(repo_dir, &["watch", "--stop"]);
+    assert!(daemon.wait().unwrap().success());
+    assert!(!socket.exists());
+    assert!(!workty(repo_dir, &["watch", "--status"]).status.success());
+}
+
+#[test]
+fn test_prompt_segment() {
+    let outside = TempDir::new().unwrap();
+    let output = workty(outside.path(), &["prompt"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let wt_output = workty_success(repo_dir, &["new", "prompt-test", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_output.trim());
+
+    let segment = workty_success(&wt_path, &["--ascii", "prompt", "--timeout-ms", "5000"]);
+    assert_eq!(segment.trim(), "prompt-test");
+
+    std::fs::write(wt_path.join("new-file.txt"), "dirty").unwrap();
+    let segment = workty_success(
+        &wt_path,
+        &[
+            "--ascii",
+            "prompt",
+            "--timeout-ms",
+            "5000",
+            "--format",
+            "[{name}{dirty}]",
+        ],
+    );
+    assert_eq!(segment.trim(), "[prompt-test*]");
+}
+
+#[test]
+fn test_dirty_breakdown_and_operation_in_json() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    std::fs::write(repo_dir.join("other.txt"), "other\n").unwrap();
+    git(repo_dir, &["add", "other.txt"]);
+    git(repo_dir, &["commit", "-m", "Add other"]);
+
+    let wt_output = workty_success(repo_dir, &["new", "breakdown", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_output.trim());
+    git(&wt_path, &["config", "user.email", "test@test.com"]);
+    git(&wt_path, &["config", "user.name", "Test User"]);
+
+    std::fs::write(wt_path.join("README.md"), "# Stashed\n").unwrap();
+    git(&wt_path, &["stash"]);
+    std::fs::write(wt_path.join("README.md"), "# Staged\n").unwrap();
+    git(&wt_path, &["add", "README.md"]);
+    std::fs::write(wt_path.join("other.txt"), "modified\n").unwrap();
+    std::fs::write(wt_path.join("new.txt"), "new\n").unwrap();
+
+    // Conflicting merge in the main worktree
+    git(repo_dir, &["checkout", "-b", "side"]);
+    std::fs::write(repo_dir.join("other.txt"), "side\n").unwrap();
+    git(repo_dir, &["commit", "-am", "Side"]);
+    git(repo_dir, &["checkout", "main"]);
+    std::fs::write(repo_dir.join("other.txt"), "main\n").unwrap();
+    git(repo_dir, &["commit", "-am", "Main"]);
+    git(repo_dir, &["merge", "side"]);
+
+    let output = workty_success(repo_dir, &["list", "--json"]);
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let find = |branch: &str| {
+        parsed["worktrees"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|wt| wt["branch_short"].as_str() == Some(branch))
+            .cloned()
+            .unwrap_or_else(|| panic!("{} missing from {}", branch, output))
+    };
+
+    let wt = find("breakdown");
+    assert_eq!(wt["dirty_count"].as_u64(), Some(3));
+    assert_eq!(wt["changes"]["staged"].as_u64(), Some(1));
+    assert_eq!(wt["changes"]["modified"].as_u64(), Some(1));
+    assert_eq!(wt["changes"]["untracked"].as_u64(), Some(1));
+    assert_eq!(wt["changes"]["conflicted"].as_u64(), Some(0));
+    assert_eq!(wt["stash_count"].as_u64(), Some(1));
+    assert!(wt["operation"].is_null());
+
+    let main = find("main");
+    assert_eq!(main["changes"]["conflicted"].as_u64(), Some(1));
+    assert_eq!(main["stash_count"].as_u64(), Some(0));
+    assert_eq!(main["operation"].as_str(), Some("merge"));
+
+    let table = workty_success(repo_dir, &["--ascii", "list"]);
+    assert!(table.contains("* +1 ~1 ?1 $1"), "{}", table);
+    assert!(table.contains("* !1 merging"), "{}", table);
+}
+
+#[test]
+fn test_operation_in_progress_blocks_unsafe_actions() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let wt_output = workty_success(repo_dir, &["new", "bisect-wt", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_output.trim());
+
+    // Put main ahead so there's something to sync
+    std::fs::write(repo_dir.join("README.md"), "# Newer\n").unwrap();
+    git(repo_dir, &["commit", "-am", "Newer"]);
+
+    // A bisect leaves the index clean, so only the repository state gives it away
+    git(&wt_path, &["bisect", "start"]);
+
+    let output = workty(repo_dir, &["--json", "--yes", "rm", "bisect-wt"]);
+    assert_eq!(output.status.code(), Some(4));
+    let parsed: serde_json::Value =
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    assert_eq!(parsed["error"]["kind"].as_str(), Some("in_progress"));
+    assert!(parsed["error"]["hint"]
+        .as_str()
+        .unwrap()
+        .contains("git bisect reset"));
+
+    let output = workty(repo_dir, &["mv", "bisect-wt", "renamed"]);
+    assert_eq!(output.status.code(), Some(4));
+
+    let output = workty_success(repo_dir, &["--json", "sync", "--onto-base"]);
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        parsed["results"][0]["outcome"].as_str(),
+        Some("skipped_in_progress"),
+        "{}",
+        output
+    );
+
+    let output = workty_success(repo_dir, &["--json", "--yes", "clean", "--merged"]);
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        parsed["candidates"][0]["operation"].as_str(),
+        Some("bisect")
+    );
+    assert_eq!(parsed["skipped"][0]["reason"].as_str(), Some("bisecting"));
+    assert!(wt_path.exists());
+
+    let listing = workty_success(repo_dir, &["--ascii", "list"]);
+    assert!(listing.contains("bisecting"), "{}", listing);
+
+    workty_success(repo_dir, &["--yes", "rm", "bisect-wt", "--force"]);
+    assert!(!wt_path.exists());
+}
+
+#[test]
+fn test_sync_never_touches_a_users_rebase() {
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let wt_output = workty_success(repo_dir, &["new", "rebasing", "--print-path"]);
+    let wt_path = std::path::PathBuf::from(wt_output.trim());
+
+    std::fs::write(wt_path.join("README.md"), "# Branch\n").unwrap();
+    git(&wt_path, &["commit", "-am", "Branch"]);
+    std::fs::write(repo_dir.join("README.md"), "# Main\n").unwrap();
+    git(repo_dir, &["commit", "-am", "Main"]);
+
+    // The user's own rebase stops on a conflict, with HEAD already on main
+    git(&wt_path, &["rebase", "main"]);
+    let state = || workty_success(repo_dir, &["--ascii", "list"]);
+    assert!(state().contains("rebasing"), "{}", state());
+
+    let result = |args: &[&str]| {
+        let output = workty_success(repo_dir, args);
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        parsed["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["name"].as_str() == Some("rebasing"))
+            .cloned()
+            .unwrap()
+    };
+
+    let skipped = result(&["--json", "sync", "--onto-base"]);
+    assert_eq!(skipped["outcome"].as_str(), Some("skipped_in_progress"));
+
+    let forced = result(&["--json", "sync", "--onto-base", "--force"]);
+    assert_eq!(forced["outcome"].as_str(), Some("failed"), "{}", forced);
+    assert!(forced["conflicts"].as_array().unwrap().is_empty());
+
+    // The rebase and its conflict are still there for the user to finish
+    assert!(state().contains("rebasing"), "{}", state());
+    let conflicts = git(&wt_path, &["diff", "--name-only", "--diff-filter=U"]);
+    assert_eq!(conflicts.trim(), "README.md");
+
+    // Same for a merge, which keeps the branch checked out and isn't behind base
+    git(&wt_path, &["rebase", "--abort"]);
+    git(&wt_path, &["merge", "main"]);
+    assert!(state().contains("merging"), "{}", state());
+
+    let skipped = result(&["--json", "sync", "--onto-base"]);
+    assert_eq!(skipped["outcome"].as_str(), Some("skipped_in_progress"));
+    let forced = result(&["--json", "sync", "--onto-base", "--force"]);
+    assert_eq!(forced["outcome"].as_str(), Some("failed"), "{}", forced);
+    assert!(state().contains("merging"), "{}", state());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_edit_keeps_invalid_edits_out_of_the_live_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let repo_dir = temp.path();
+
+    git_init_repo(repo_dir);
+    let config = repo_dir.join(".git/workty.toml");
+    std::fs::write(&config, "layout = \"flat\"\n").unwrap();
+
+    let editor = |name: &str, line: &str| {
+        let script = temp.path().join(name);
+        std::fs::write(&script, format!("#!/bin/sh\necho '{}' > \"$1\"\n", line)).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    };
+    let edit = |script: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_git-workty"))
+            .current_dir(repo_dir)
+            .env("VISUAL", script)
+            .args(["config", "edit"])
+            .output()
+            .unwrap()
+    };
+
+    let output = edit(&editor("bad-editor", "layout = "));
+    assert!(!output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(&config).unwrap(),
+        "layout = \"flat\"\n"
+    );
+    let draft = repo_dir.join(".git/workty.edit.toml");
+    assert!(draft.exists());
+    workty_success(repo_dir, &["list"]);
+
+    let output = edit(&editor("good-editor", "layout = \"nested\""));
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(&config).unwrap(),
+        "layout = \"nested\"\n"
+    );
+    assert!(!draft.exists());
+}